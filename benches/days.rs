@@ -0,0 +1,44 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use advent_of_code_2019::days::{day1, day11, day13, day15, day17, day19, day2, day21, day25};
+use advent_of_code_2019::days::{day5, day9};
+use advent_of_code_2019::solution::{input_path, Solution};
+
+/*
+ A group per day: parsing and each part, on the day's real input.  Days without an input in
+ ./input are skipped, so this runs whatever subset is around.
+
+   cargo bench --bench days -- "day 13"
+*/
+
+fn bench_day<S: Solution>(c: &mut Criterion, solution: &S) {
+    let Ok(input) = fs::read_to_string(input_path(solution.day())) else {
+        return;
+    };
+    let parsed = solution.parse(&input);
+    let mut group = c.benchmark_group(format!("day {}", solution.day()));
+    group.bench_function("parse", |b| b.iter(|| solution.parse(&input)));
+    group.bench_function("part one", |b| b.iter(|| solution.part_one(&parsed)));
+    group.bench_function("part two", |b| b.iter(|| solution.part_two(&parsed)));
+    group.finish();
+}
+
+fn days_benchmark(c: &mut Criterion) {
+    bench_day(c, &day1::Day1);
+    bench_day(c, &day2::Day2);
+    bench_day(c, &day5::Day5);
+    bench_day(c, &day9::Day9);
+    // Default options, so no pictures or animations
+    bench_day(c, &day11::Day11::default());
+    bench_day(c, &day13::Day13::default());
+    bench_day(c, &day15::Day15::default());
+    bench_day(c, &day17::Day17);
+    bench_day(c, &day19::Day19::default());
+    bench_day(c, &day21::Day21);
+    bench_day(c, &day25::Day25);
+}
+
+criterion_group!(benches, days_benchmark);
+criterion_main!(benches);
@@ -1,7 +1,7 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
 use lazy_static::lazy_static;
 
-use advent_of_code_2019::vm::VM;
+use advent_of_code_2019::vm::{try_decode_opcode, VM};
 
 lazy_static! {
     static ref QUINE: Vec<isize> =
@@ -14,6 +14,15 @@ lazy_static! {
 
         (input, 2)
     };
+    static ref DAY_5: (Vec<isize>, isize) = {
+        let input = include_str!("../input/day5")
+            .trim()
+            .split(",")
+            .map(|x| x.parse::<isize>().unwrap())
+            .collect::<Vec<isize>>();
+
+        (input, 5)
+    };
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -32,5 +41,137 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+// Every opcode, in a mix of parameter modes, plus a couple that aren't opcodes at all
+fn decode_benchmark(c: &mut Criterion) {
+    let raw: Vec<isize> = vec![
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 99, 1101, 1002, 203, 104, 1105, 1106, 21107, 22208, 209, 0, 42,
+    ];
+    c.bench_function("decode opcodes", |b| {
+        b.iter(|| {
+            raw.iter()
+                .filter_map(|value| try_decode_opcode(black_box(*value)))
+                .count()
+        })
+    });
+}
+
+// One add, with both inputs and the output in each parameter mode
+fn parameter_benchmark(c: &mut Criterion) {
+    let programs: [(&str, Vec<isize>); 3] = [
+        ("position", vec![1, 5, 6, 7, 99, 10, 20, 0]),
+        ("immediate", vec![1101, 10, 20, 7, 99, 0, 0, 0]),
+        ("relative", vec![22201, 5, 6, 7, 99, 10, 20, 0]),
+    ];
+    let mut group = c.benchmark_group("parameter modes");
+    for (mode, program) in &programs {
+        group.bench_with_input(BenchmarkId::from_parameter(mode), program, |b, program| {
+            b.iter_batched(
+                || VM::new(program.clone()),
+                |mut vm| vm.step().unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// Touching memory past the end of the program, which grows it to fit
+fn memory_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory growth");
+    for address in [1_000usize, 100_000, 1_000_000] {
+        group.bench_with_input(
+            BenchmarkId::new("write", address),
+            &address,
+            |b, address| {
+                b.iter_batched(
+                    || VM::new(vec![99]),
+                    |mut vm| vm.set_memory(*address, 1),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("read", address), &address, |b, address| {
+            b.iter_batched(
+                || VM::new(vec![99]),
+                |mut vm| vm.get_memory(*address),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/*
+ Cloning day 9's VM after a run (memory grown and all), then changing one address: with its
+ memory as a plain Vec, as usual, and shared in pages (share_memory).
+*/
+fn clone_benchmark(c: &mut Criterion) {
+    let mut flat = VM::new(DAY_9.0.clone());
+    flat.push_input(DAY_9.1);
+    flat.run();
+    let mut shared = flat.clone();
+    shared.share_memory();
+
+    let mut group = c.benchmark_group("clone");
+    for (name, vm) in [("flat", &flat), ("shared", &shared)] {
+        group.bench_function(name, |b| b.iter(|| black_box(vm).clone()));
+        group.bench_function(format!("{name} then write"), |b| {
+            b.iter(|| {
+                let mut vm = black_box(vm).clone();
+                vm.set_memory(0, 1);
+                vm
+            })
+        });
+    }
+    group.finish();
+}
+
+/*
+ The same programs run by differently set up VMs, to see what each costs:
+   plain              just run
+   self modification  tracking writes to executed code (track_self_modification)
+   budgeted           run in slices of try_run_for, as day 25 does to guard against hangs
+   shared memory      memory in copy on write pages (share_memory)
+*/
+fn configuration_benchmark(c: &mut Criterion) {
+    type Run = fn(&mut VM);
+    let configurations: [(&str, Run); 4] = [
+        ("plain", |vm| vm.run()),
+        ("self modification", |vm| {
+            vm.track_self_modification();
+            vm.run();
+        }),
+        ("shared memory", |vm| {
+            vm.share_memory();
+            vm.run();
+        }),
+        ("budgeted", |vm| while !vm.try_run_for(10_000).unwrap() {}),
+    ];
+    let programs = [("day 5", &*DAY_5), ("day 9", &*DAY_9)];
+
+    let mut group = c.benchmark_group("configurations");
+    for (name, run) in configurations {
+        for (day, (program, input)) in programs {
+            group.bench_with_input(BenchmarkId::new(name, day), program, |b, program| {
+                b.iter(|| {
+                    let mut vm = VM::new(program.clone());
+                    vm.push_input(*input);
+                    run(&mut vm);
+                    vm.pop_output()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    decode_benchmark,
+    parameter_benchmark,
+    memory_benchmark,
+    clone_benchmark,
+    configuration_benchmark
+);
 criterion_main!(benches);
@@ -0,0 +1,25 @@
+#![no_main]
+
+// Any memory and any input should only ever produce a VMError, never a panic or an abort.
+
+use advent_of_code_2019::vm::VM;
+use libfuzzer_sys::fuzz_target;
+
+const MAX_STEPS: usize = 10_000;
+
+fuzz_target!(|data: (Vec<isize>, Vec<isize>)| {
+    let (memory, input) = data;
+    let mut vm = VM::new(memory);
+    vm.track_self_modification();
+    for value in input {
+        vm.push_input(value);
+    }
+    for _ in 0..MAX_STEPS {
+        if vm.finished() || vm.needs_input() || vm.pointer() >= vm.memory().len() {
+            break;
+        }
+        if vm.step().is_err() {
+            break;
+        }
+    }
+});
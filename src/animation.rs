@@ -0,0 +1,273 @@
+/*
+
+Record a grid simulation frame by frame, and save it as an animated GIF or a numbered sequence
+of PNGs.
+
+Frames are kept as grids of colours, and only drawn (by a Renderer) when saving, once we know
+the bounds of the whole animation.  That way the picture doesn't jump around as the grid grows
+(as day 11's hull does while the robot wanders off), and every frame comes out the same size.
+
+*/
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, ImageResult, Rgb, RgbImage};
+
+use crate::grid::{Bounds, Grid, SparseGrid};
+use crate::render::{Palette, Renderer};
+use crate::Point;
+
+/// Collects frames of a grid of `T`, coloured by `palette`
+pub struct Recorder<T, P: Palette<T>> {
+    palette: P,
+    scale: u32,
+    every: usize,
+    y_up: bool,
+    background: Rgb<u8>,
+    delay_ms: u32,
+    captured: usize,
+    frames: Vec<SparseGrid<Rgb<u8>>>,
+    bounds: Option<Bounds>,
+    _tile: std::marker::PhantomData<T>,
+}
+
+impl<T, P: Palette<T>> Recorder<T, P> {
+    /// `palette` gives the colour of each tile.  Defaults to one pixel per cell, every frame
+    /// kept, y growing downwards, a black background and 50ms per frame.
+    #[must_use]
+    pub fn new(palette: P) -> Self {
+        Recorder {
+            palette,
+            scale: 1,
+            every: 1,
+            y_up: false,
+            background: Rgb([0, 0, 0]),
+            delay_ms: 50,
+            captured: 0,
+            frames: vec![],
+            bounds: None,
+            _tile: std::marker::PhantomData,
+        }
+    }
+
+    /// Draw each cell as a `scale` x `scale` square
+    #[must_use]
+    pub fn scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Only keep one in every `every` frames captured, starting with the first
+    #[must_use]
+    pub fn every(mut self, every: usize) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    /// Whether y grows upwards (as on day 11's hull) rather than down the screen
+    #[must_use]
+    pub fn y_up(mut self, y_up: bool) -> Self {
+        self.y_up = y_up;
+        self
+    }
+
+    /// Colour for anything not in the frame
+    #[must_use]
+    pub fn background(mut self, background: Rgb<u8>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// How long each frame shows for in a GIF
+    #[must_use]
+    pub fn delay_ms(mut self, delay_ms: u32) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    /// Offer up the current state of the grid, which is kept unless it's being skipped
+    pub fn capture<'a, I>(&mut self, cells: I)
+    where
+        I: IntoIterator<Item = (&'a Point<isize>, &'a T)>,
+        T: 'a,
+    {
+        let skip = !self.captured.is_multiple_of(self.every);
+        self.captured += 1;
+        if skip {
+            return;
+        }
+        let mut frame = SparseGrid::new().with_y_up(self.y_up);
+        for (point, tile) in cells {
+            frame.set(*point, self.palette.colour(tile));
+        }
+        if let Some(Bounds { min, max }) = frame.bounds() {
+            match &mut self.bounds {
+                Some(bounds) => {
+                    bounds.extend(&min);
+                    bounds.extend(&max);
+                }
+                None => self.bounds = Some(Bounds { min, max }),
+            }
+        }
+        self.frames.push(frame);
+    }
+
+    /// Capture for grids kept in a HashMap, which is most of them
+    pub fn capture_map(&mut self, map: &HashMap<Point<isize>, T>) {
+        self.capture(map.iter());
+    }
+
+    /// Frames kept so far
+    #[must_use]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Every kept frame, drawn and scaled
+    #[must_use]
+    pub fn frames(&self) -> Vec<RgbImage> {
+        let mut renderer = Renderer::new()
+            .scale(self.scale)
+            .background(self.background);
+        // Without any bounds, every frame was empty
+        if let Some(bounds) = &self.bounds {
+            renderer = renderer.bounds(bounds.clone());
+        }
+        let colour = |colour: &Rgb<u8>| *colour;
+        self.frames
+            .iter()
+            .map(|frame| renderer.render(frame, &colour))
+            .collect()
+    }
+
+    /// Save as an animated GIF that loops forever
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be written
+    pub fn save_gif<Q: AsRef<Path>>(&self, path: Q) -> ImageResult<()> {
+        let mut encoder = GifEncoder::new(File::create(path)?);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_numer_denom_ms(self.delay_ms, 1);
+        let frames = self.frames().into_iter().map(|img| {
+            let rgba = DynamicImage::ImageRgb8(img).to_rgba8();
+            Frame::from_parts(rgba, 0, 0, delay)
+        });
+        encoder.encode_frames(frames)
+    }
+
+    /// Save every frame as `{prefix}_0000.png`, `{prefix}_0001.png` and so on, returning the
+    /// paths written
+    ///
+    /// # Errors
+    ///
+    /// If any of the files can't be written
+    pub fn save_png_sequence(&self, prefix: &str) -> ImageResult<Vec<PathBuf>> {
+        let mut paths = vec![];
+        for (number, img) in self.frames().into_iter().enumerate() {
+            let path = PathBuf::from(format!("{prefix}_{number:04}.png"));
+            img.save(&path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifDecoder;
+    use image::AnimationDecoder;
+
+    const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+    const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+    fn palette(tile: &bool) -> Rgb<u8> {
+        if *tile {
+            WHITE
+        } else {
+            BLACK
+        }
+    }
+
+    fn single(x: isize, y: isize) -> HashMap<Point<isize>, bool> {
+        HashMap::from([(Point { x, y }, true)])
+    }
+
+    #[test]
+    fn test_frames_share_bounds() {
+        let mut recorder = Recorder::new(palette);
+        recorder.capture_map(&single(0, 0));
+        recorder.capture_map(&single(2, 1));
+        let frames = recorder.frames();
+        assert_eq!(frames.len(), 2);
+        for frame in &frames {
+            assert_eq!(frame.dimensions(), (3, 2));
+        }
+        assert_eq!(*frames[0].get_pixel(0, 0), WHITE);
+        assert_eq!(*frames[0].get_pixel(2, 1), BLACK);
+        assert_eq!(*frames[1].get_pixel(2, 1), WHITE);
+    }
+
+    #[test]
+    fn test_y_up_and_scale() {
+        let mut recorder = Recorder::new(palette)
+            .y_up(true)
+            .scale(4)
+            .background(Rgb([0, 0, 255]));
+        recorder.capture_map(&HashMap::from([
+            (Point { x: 0, y: 1 }, true),
+            (Point { x: 1, y: 0 }, false),
+        ]));
+        let frame = &recorder.frames()[0];
+        assert_eq!(frame.dimensions(), (8, 8));
+        // y = 1 is the top row
+        assert_eq!(*frame.get_pixel(3, 3), WHITE);
+        assert_eq!(*frame.get_pixel(7, 7), BLACK);
+        assert_eq!(*frame.get_pixel(7, 0), Rgb([0, 0, 255]));
+    }
+
+    #[test]
+    fn test_every() {
+        let mut recorder = Recorder::new(palette).every(3);
+        for x in 0..7 {
+            recorder.capture_map(&single(x, 0));
+        }
+        // Frames 0, 3 and 6
+        assert_eq!(recorder.frame_count(), 3);
+        let frames = recorder.frames();
+        assert_eq!(*frames[1].get_pixel(3, 0), WHITE);
+        assert_eq!(*frames[2].get_pixel(6, 0), WHITE);
+    }
+
+    #[test]
+    fn test_save() {
+        let mut recorder = Recorder::new(palette).scale(2);
+        for x in 0..3 {
+            recorder.capture_map(&single(x, x));
+        }
+        let directory = std::env::temp_dir().join(format!("animation_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let gif = directory.join("test.gif");
+        recorder.save_gif(&gif).unwrap();
+        let decoder = GifDecoder::new(File::open(&gif).unwrap()).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].buffer().dimensions(), (6, 6));
+
+        let prefix = directory.join("frame");
+        let paths = recorder
+            .save_png_sequence(prefix.to_str().unwrap())
+            .unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[2].ends_with("frame_0002.png"));
+        let last = image::open(&paths[2]).unwrap().to_rgb8();
+        assert_eq!(*last.get_pixel(5, 5), WHITE);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
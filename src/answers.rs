@@ -0,0 +1,206 @@
+/*
+
+Known answers, to catch a change (usually to the VM) that quietly breaks a day that used to work.
+
+They live in answers.txt at the top of the repository, next to input/, one answer per line:
+
+    # day part answer
+    2 one 6327510
+    2 two 4112
+
+Everything after the part is the answer, spaces and all.  `aoc --check` runs every day and
+compares, as does the known answers test here.
+
+*/
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+
+use crate::solution::Report;
+
+pub const ANSWERS_PATH: &str = "./answers.txt";
+
+/// Expected answers, by day and part (1 or 2)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Answers {
+    expected: BTreeMap<(u32, u8), String>,
+}
+
+/// An answer that isn't what it used to be
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub day: u32,
+    pub part: u8,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let part = if self.part == 1 { "one" } else { "two" };
+        writeln!(f, "Day {} part {part}:", self.day)?;
+        writeln!(f, "- {}", self.expected)?;
+        write!(f, "+ {}", self.actual)
+    }
+}
+
+impl Answers {
+    /// Read answers in the format above
+    ///
+    /// # Errors
+    ///
+    /// If a line isn't a day, a part and an answer, naming the line
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut answers = Answers::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.splitn(3, ' ');
+            let (Some(day), Some(part), Some(answer)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(format!(
+                    "Line {}: expected a day, part and answer",
+                    number + 1
+                ));
+            };
+            let day = day
+                .parse()
+                .map_err(|_| format!("Line {}: {day} isn't a day", number + 1))?;
+            let part = match part {
+                "one" => 1,
+                "two" => 2,
+                _ => return Err(format!("Line {}: {part} isn't one or two", number + 1)),
+            };
+            answers
+                .expected
+                .insert((day, part), answer.trim().to_string());
+        }
+        Ok(answers)
+    }
+
+    /// Read answers.txt
+    ///
+    /// # Errors
+    ///
+    /// If it can't be read, or doesn't parse
+    pub fn load() -> Result<Self, String> {
+        let text = fs::read_to_string(ANSWERS_PATH)
+            .map_err(|error| format!("Couldn't read {ANSWERS_PATH}: {error}"))?;
+        Answers::parse(&text)
+    }
+
+    #[must_use]
+    pub fn get(&self, day: u32, part: u8) -> Option<&str> {
+        self.expected.get(&(day, part)).map(String::as_str)
+    }
+
+    /// Whether there's anything to check a day's answers against
+    #[must_use]
+    pub fn knows(&self, day: u32) -> bool {
+        self.get(day, 1).is_some() || self.get(day, 2).is_some()
+    }
+
+    /// Every answer in `report` that differs from the known one.  Parts with no known answer
+    /// can't be wrong.
+    #[must_use]
+    pub fn check(&self, report: &Report) -> Vec<Mismatch> {
+        [(1, &report.part_one), (2, &report.part_two)]
+            .into_iter()
+            .filter_map(|(part, actual)| {
+                let expected = self.get(report.day, part)?;
+                (expected != actual).then(|| Mismatch {
+                    day: report.day,
+                    part,
+                    expected: expected.to_string(),
+                    actual: actual.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days;
+    use crate::solution::input_path;
+    use std::time::Duration;
+
+    fn report(day: u32, part_one: &str, part_two: &str) -> Report {
+        Report {
+            day,
+            parse: Duration::ZERO,
+            part_one: part_one.to_string(),
+            part_one_time: Duration::ZERO,
+            part_two: part_two.to_string(),
+            part_two_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let answers =
+            Answers::parse("# day part answer\n\n2 one 6327510\n25 two Merry Christmas!\n")
+                .unwrap();
+        assert_eq!(answers.get(2, 1), Some("6327510"));
+        assert_eq!(answers.get(2, 2), None);
+        assert_eq!(answers.get(25, 2), Some("Merry Christmas!"));
+        assert!(answers.knows(25));
+        assert!(!answers.knows(9));
+
+        assert_eq!(
+            Answers::parse("2 one"),
+            Err("Line 1: expected a day, part and answer".to_string())
+        );
+        assert_eq!(
+            Answers::parse("\ntwo one 1"),
+            Err("Line 2: two isn't a day".to_string())
+        );
+        assert_eq!(
+            Answers::parse("2 three 1"),
+            Err("Line 1: three isn't one or two".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let answers = Answers::parse("9 one 3518157894\n9 two 80379").unwrap();
+        assert!(answers.check(&report(9, "3518157894", "80379")).is_empty());
+        assert!(answers.check(&report(13, "1", "2")).is_empty());
+
+        let mismatches = answers.check(&report(9, "3518157894", "80380"));
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "Day 9 part two:\n- 80379\n+ 80380"
+        );
+    }
+
+    // Every day with an input, against answers.txt
+    #[test]
+    fn test_known_answers() {
+        let answers = Answers::load().unwrap();
+        let mut mismatches = vec![];
+        for solution in days::all() {
+            let Ok(input) = fs::read_to_string(input_path(solution.day())) else {
+                continue;
+            };
+            assert!(
+                answers.knows(solution.day()),
+                "No known answers for day {}",
+                solution.day()
+            );
+            mismatches.extend(answers.check(&solution.run(&input)));
+        }
+        let diff: Vec<String> = mismatches.iter().map(Mismatch::to_string).collect();
+        assert!(
+            mismatches.is_empty(),
+            "Answers changed:\n{}",
+            diff.join("\n")
+        );
+    }
+}
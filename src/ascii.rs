@@ -0,0 +1,80 @@
+/*
+
+Several of the later puzzles (17, 21, 25) run "ASCII capable" Intcode: output is mostly text, one
+character per value, and input is lines of text ending in a newline.  Anything that doesn't fit
+in ASCII (usually the actual answer) is passed through as a plain number.
+
+*/
+
+use crate::vm::VM;
+
+/// Queue `line` followed by a newline, to be read after any input already waiting
+pub fn queue_line(vm: &mut VM, line: &str) {
+    vm.queue_input(line.bytes().chain([b'\n']).map(isize::from));
+}
+
+fn is_ascii(value: isize) -> bool {
+    (0..=127).contains(&value)
+}
+
+/// Everything a VM has output so far, split into text and everything else
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AsciiOutput {
+    pub text: String,
+    /// Values outside the ASCII range, in the order they were output
+    pub values: Vec<isize>,
+}
+
+impl AsciiOutput {
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.text.lines()
+    }
+
+    /// The last non-ASCII value, which is where puzzles tend to put their answer
+    #[must_use]
+    pub fn value(&self) -> Option<isize> {
+        self.values.last().copied()
+    }
+}
+
+/// Drain the VM's output queue
+pub fn take_output(vm: &mut VM) -> AsciiOutput {
+    let mut output = AsciiOutput::default();
+    while let Some(value) = vm.pop_front_output() {
+        if is_ascii(value) {
+            output.text.push(char::from(value as u8));
+        } else {
+            output.values.push(value);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_output() {
+        // Prints "Hi" on one line and 1000 on the next
+        let mut vm = VM::new(vec![104, 72, 104, 105, 104, 10, 104, 1000, 99]);
+        vm.run();
+        let output = take_output(&mut vm);
+        assert_eq!(output.text, "Hi\n");
+        assert_eq!(output.values, vec![1000]);
+        assert_eq!(output.value(), Some(1000));
+        assert_eq!(output.lines().collect::<Vec<_>>(), vec!["Hi"]);
+        assert!(!vm.has_output());
+    }
+
+    #[test]
+    fn test_queue_line() {
+        // Echo input forever
+        let mut vm = VM::new(vec![3, 100, 4, 100, 1105, 1, 0]);
+        queue_line(&mut vm, "ab");
+        queue_line(&mut vm, "c");
+        vm.run();
+        assert_eq!(take_output(&mut vm).text, "ab\nc\n");
+        assert!(vm.needs_input());
+    }
+}
@@ -0,0 +1,208 @@
+/*
+
+A small assembler for the syntax `disasm` prints, so hand written test programs don't have to be
+worked out as raw numbers.
+
+    ; comments run to the end of the line
+    IN [rb+1]
+    MUL [rb+1], 2, [20]
+    OUT [20]
+    HALT
+    DATA 0, 0
+
+`[n]` is position mode, a bare number is immediate, and `[rb+n]` / `[rb-n]` is relative.  A
+leading `address:` (as in disassembly listings) is ignored.
+
+*/
+
+use std::fmt;
+
+use crate::disasm::{writes_to_param, Instruction, Param};
+use crate::vm::OC;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsmError {
+    /// 1 based, like an editor
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+fn opcode_from_mnemonic(mnemonic: &str) -> Option<OC> {
+    let opcode = match mnemonic.to_ascii_uppercase().as_str() {
+        "ADD" => OC::Add,
+        "MUL" => OC::Mul,
+        "IN" => OC::Input,
+        "OUT" => OC::Output,
+        "JNZ" => OC::JumpIfTrue,
+        "JZ" => OC::JumpIfFalse,
+        "LT" => OC::LessThan,
+        "EQ" => OC::Equals,
+        "ARB" => OC::RelativeBaseOffset,
+        "HALT" => OC::End,
+        _ => return None,
+    };
+    Some(opcode)
+}
+
+fn parse_number(text: &str) -> Result<isize, String> {
+    text.trim()
+        .parse::<isize>()
+        .map_err(|_| format!("expected a number, got {text:?}"))
+}
+
+fn parse_param(text: &str) -> Result<Param, String> {
+    let text = text.trim();
+    let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) else {
+        return parse_number(text).map(Param::Immediate);
+    };
+    let inner = inner.trim();
+    match inner.strip_prefix("rb") {
+        Some(offset) => {
+            let offset = offset.trim();
+            let offset = offset.strip_prefix('+').unwrap_or(offset);
+            parse_number(offset).map(Param::Relative)
+        }
+        None => parse_number(inner).map(Param::Position),
+    }
+}
+
+/// Everything on a line that isn't a comment or an address label
+fn strip_line(line: &str) -> &str {
+    let line = line.split(';').next().unwrap_or_default().trim();
+    match line.split_once(':') {
+        Some((address, rest)) if address.trim().parse::<usize>().is_ok() => rest.trim(),
+        _ => line,
+    }
+}
+
+/// Assemble source into memory, ready for `VM::new`.
+///
+/// # Errors
+///
+/// The first line that isn't a valid instruction or DATA directive.
+pub fn assemble(source: &str) -> Result<Vec<isize>, AsmError> {
+    let mut memory = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| AsmError {
+            line: index + 1,
+            message,
+        };
+        let line = strip_line(line);
+        if line.is_empty() {
+            continue;
+        }
+        let (mnemonic, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let operands: Vec<&str> = if rest.trim().is_empty() {
+            vec![]
+        } else {
+            rest.split(',').collect()
+        };
+
+        if mnemonic.eq_ignore_ascii_case("DATA") {
+            if operands.is_empty() {
+                return Err(error("DATA needs at least one value".to_string()));
+            }
+            for operand in operands {
+                memory.push(parse_number(operand).map_err(error)?);
+            }
+            continue;
+        }
+
+        let opcode = opcode_from_mnemonic(mnemonic)
+            .ok_or_else(|| error(format!("unknown instruction {mnemonic:?}")))?;
+        if operands.len() != opcode.parameter_count() {
+            return Err(error(format!(
+                "{} takes {} parameters, got {}",
+                opcode.mnemonic(),
+                opcode.parameter_count(),
+                operands.len()
+            )));
+        }
+        let mut params = vec![];
+        for (index, operand) in operands.iter().enumerate() {
+            let param = parse_param(operand).map_err(error)?;
+            if writes_to_param(&opcode, index) && matches!(param, Param::Immediate(_)) {
+                return Err(error(format!(
+                    "parameter {} of {} is written to, so can't be immediate",
+                    index + 1,
+                    opcode.mnemonic()
+                )));
+            }
+            params.push(param);
+        }
+        let instruction = Instruction {
+            address: memory.len(),
+            opcode,
+            params,
+        };
+        memory.extend(instruction.encode());
+    }
+    Ok(memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble;
+    use crate::generate::{any_memory, well_formed_program};
+    use proptest::prelude::*;
+    use rstest::*;
+
+    #[test]
+    fn test_assemble() {
+        let source = "
+            ; double the input
+            IN [rb+1]
+            MUL [rb+1], 2, [9]
+            4: OUT [9]   ; the listing address is ignored
+            halt
+            DATA 0
+        ";
+        assert_eq!(
+            assemble(source).unwrap(),
+            vec![203, 1, 1202, 1, 2, 9, 4, 9, 99, 0]
+        );
+    }
+
+    #[rstest]
+    #[case("NOP", 1, "unknown instruction \"NOP\"")]
+    #[case("ADD 1, 2", 1, "ADD takes 3 parameters, got 2")]
+    #[case(
+        "HALT\nIN 5",
+        2,
+        "parameter 1 of IN is written to, so can't be immediate"
+    )]
+    #[case("OUT [rb+x]", 1, "expected a number, got \"x\"")]
+    #[case("DATA", 1, "DATA needs at least one value")]
+    fn test_assemble_errors(#[case] source: &str, #[case] line: usize, #[case] message: &str) {
+        assert_eq!(
+            assemble(source),
+            Err(AsmError {
+                line,
+                message: message.to_string()
+            })
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_disassemble_then_assemble(memory in any_memory()) {
+            prop_assert_eq!(assemble(&disassemble(&memory)), Ok(memory));
+        }
+
+        #[test]
+        fn prop_assemble_then_disassemble(memory in well_formed_program()) {
+            let listing = disassemble(&memory);
+            let assembled = assemble(&listing).unwrap();
+            prop_assert_eq!(disassemble(&assembled), listing);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+/*
+
+Running VMs as async tasks, for when there are lots of them talking to each other (or to the
+network) and a thread each is too heavy.  Only built with the `async` feature:
+
+    cargo test --features async
+
+`drive` runs a VM until it halts, taking input from a tokio channel and sending output to
+another.  Where the VM would stop for input it awaits the next value instead, so a waiting VM
+costs nothing until something arrives, with no polling `needs_input()`.  Output is sent after
+every slice of instructions, and always before waiting for input, so one VM's answer reaches
+the next before it can be wanted.  Between slices a busy VM yields, so others on the same
+runtime still get a turn, even on a single thread.
+
+When the VM halts its output sender is dropped, so whoever's reading sees the channel close.
+
+*/
+
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task;
+
+use crate::vm::{DriveError, VM};
+
+// Instructions run between yields to the runtime
+const SLICE: usize = 10_000;
+
+/// Run `vm` until it halts, awaiting `input` whenever it needs more and sending its output to
+/// `output` as it goes.  Anything already queued on the VM is read before `input`.  Returns the
+/// VM as it finished.
+///
+/// # Errors
+///
+/// If the program goes wrong, or either channel closes while it's still needed.
+pub async fn drive(
+    mut vm: VM,
+    mut input: Receiver<isize>,
+    output: Sender<isize>,
+) -> Result<VM, DriveError> {
+    loop {
+        // Whatever it output before going wrong still gets sent
+        let ran = vm.try_run_for(SLICE);
+        while let Some(value) = vm.pop_front_output() {
+            output
+                .send(value)
+                .await
+                .map_err(|_| DriveError::OutputClosed { value })?;
+        }
+        let stopped = ran?;
+        if vm.needs_input() {
+            let Some(value) = input.recv().await else {
+                return Err(DriveError::InputClosed {
+                    pointer: vm.pointer(),
+                });
+            };
+            vm.push_input(value);
+        } else if stopped {
+            return Ok(vm);
+        } else {
+            task::yield_now().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::runtime::Builder;
+    use tokio::sync::mpsc::channel;
+    use tokio::task::LocalSet;
+
+    // Only ever a single threaded runtime, with everything spawned on it locally
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        LocalSet::new().block_on(&runtime, future)
+    }
+
+    // Day 7's second feedback loop example: the phase, then signals in and out until it halts
+    const AMPLIFIER: &[isize] = &[
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    #[test]
+    fn test_echo() {
+        // Outputs whatever it reads, forever
+        let (input, from_test) = channel(1);
+        let (to_test, mut output) = channel(1);
+        let echo = VM::new(vec![3, 7, 4, 7, 1105, 1, 0, 0]);
+        let result = block_on(async move {
+            let vm = task::spawn_local(drive(echo, from_test, to_test));
+            for value in [5, -3, 7] {
+                input.send(value).await.unwrap();
+                assert_eq!(output.recv().await, Some(value));
+            }
+            drop(input);
+            vm.await.unwrap()
+        });
+        assert_eq!(result.unwrap_err(), DriveError::InputClosed { pointer: 0 });
+    }
+
+    #[test]
+    fn test_amplifier_loop() {
+        // Five amplifiers in a ring, each on its own task, with the last one's output recorded on
+        // its way back round to the first
+        let signal = block_on(async {
+            let (first, mut input) = channel(2);
+            let mut amplifiers = vec![];
+            for phase in [9, 8, 7, 6, 5] {
+                let (sender, receiver) = channel(2);
+                let mut amplifier = VM::new(AMPLIFIER.to_vec());
+                amplifier.push_input(phase);
+                amplifiers.push(task::spawn_local(drive(amplifier, input, sender)));
+                input = receiver;
+            }
+            first.send(0).await.unwrap();
+            let mut last = None;
+            while let Some(signal) = input.recv().await {
+                last = Some(signal);
+                // Once the first has halted there's nowhere left to send it
+                let _ = first.send(signal).await;
+            }
+            for amplifier in amplifiers {
+                assert!(amplifier.await.unwrap().unwrap().finished());
+            }
+            last
+        });
+        assert_eq!(signal, Some(139_629_729));
+    }
+
+    #[test]
+    fn test_busy_vms_take_turns() {
+        // Jumps to itself forever, so on one thread the other task would never get a turn if it
+        // didn't yield
+        let answer = block_on(async {
+            let (_input, receiver) = channel(1);
+            let (sender, _output) = channel(1);
+            let forever = task::spawn_local(drive(VM::new(vec![1105, 1, 0]), receiver, sender));
+            let answer = task::spawn_local(async { 42 }).await.unwrap();
+            forever.abort();
+            answer
+        });
+        assert_eq!(answer, 42);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (_input, receiver) = channel(1);
+        let (sender, output) = channel(1);
+        drop(output);
+        let result = block_on(drive(VM::new(vec![104, 1, 99]), receiver, sender));
+        assert_eq!(result.unwrap_err(), DriveError::OutputClosed { value: 1 });
+
+        let (_input, receiver) = channel(1);
+        let (sender, mut output) = channel(1);
+        let result = block_on(async move {
+            let vm = task::spawn_local(drive(VM::new(vec![104, 1, 42]), receiver, sender));
+            assert_eq!(output.recv().await, Some(1));
+            vm.await.unwrap()
+        });
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid opcode 42 at 2".to_string()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::fs;
+use std::io::Read;
+use std::process::exit;
+use std::time::Duration;
+
+use log::LevelFilter;
+use simple_logger::SimpleLogger;
+
+use advent_of_code_2019::answers::{Answers, Mismatch};
+use advent_of_code_2019::days;
+use advent_of_code_2019::solution::{input_path, Report, Runner};
+
+/*
+ Run any or all of the days, and show the answers and timings in a table (or as JSON).
+
+   aoc                      every day with an input in ./input
+   aoc 2 9 13               just those days
+   aoc 9 --input other      day 9 on a different input
+   aoc 9 --stdin            day 9 on whatever's piped in
+   aoc --json               JSON rather than a table
+   aoc --check              also compare against answers.txt, failing if any have changed
+
+ Days log at warn and above, to keep the table readable.  RUST_LOG overrides that as usual.
+*/
+
+const USAGE: &str = "Usage: aoc [DAY...] [--input PATH | --stdin] [--json] [--check]";
+
+#[derive(Debug, Default)]
+struct Options {
+    days: Vec<u32>,
+    input: Option<String>,
+    stdin: bool,
+    json: bool,
+    check: bool,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => match args.next() {
+                Some(path) => options.input = Some(path),
+                None => return Err("--input needs a path".to_string()),
+            },
+            "--stdin" => options.stdin = true,
+            "--json" => options.json = true,
+            "--check" => options.check = true,
+            "all" => {}
+            day => match day.parse::<u32>() {
+                Ok(day) => options.days.push(day),
+                Err(_) => return Err(format!("Don't know what {day} means")),
+            },
+        }
+    }
+    if (options.input.is_some() || options.stdin) && options.days.len() != 1 {
+        return Err("--input and --stdin need exactly one day".to_string());
+    }
+    if options.input.is_some() && options.stdin {
+        return Err("Use --input or --stdin, not both".to_string());
+    }
+    Ok(options)
+}
+
+// Input from wherever we were told, or None if a day's usual input isn't there
+fn read_input(options: &Options, day: u32) -> Result<Option<String>, String> {
+    if options.stdin {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|error| format!("Couldn't read stdin: {error}"))?;
+        return Ok(Some(input));
+    }
+    if let Some(path) = &options.input {
+        return fs::read_to_string(path)
+            .map(Some)
+            .map_err(|error| format!("Couldn't read {path}: {error}"));
+    }
+    Ok(fs::read_to_string(input_path(day)).ok())
+}
+
+// Multi-line answers (pictures, mostly) don't fit in a table, so they go underneath it
+fn cell(answer: &str) -> &str {
+    if answer.contains('\n') {
+        "(see below)"
+    } else {
+        answer
+    }
+}
+
+fn print_table(reports: &[Report], missing: &[u32]) {
+    println!(
+        "{:>3}  {:<20} {:>12}  {:<20} {:>12}",
+        "Day", "Part one", "Time", "Part two", "Time"
+    );
+    for report in reports {
+        println!(
+            "{:>3}  {:<20} {:>12}  {:<20} {:>12}",
+            report.day,
+            cell(&report.part_one),
+            format!("{:.2?}", report.part_one_time),
+            cell(&report.part_two),
+            format!("{:.2?}", report.part_two_time),
+        );
+    }
+    let total: Duration = reports.iter().map(Report::total).sum();
+    println!("Total time, parsing included: {total:.2?}");
+    for day in missing {
+        println!("Skipped day {day}, as there's no {}", input_path(*day));
+    }
+    for report in reports {
+        for (part, answer) in [("one", &report.part_one), ("two", &report.part_two)] {
+            if answer.contains('\n') {
+                println!("\nDay {} part {part}:\n{answer}", report.day);
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn to_json(reports: &[Report]) -> String {
+    let entries: Vec<String> = reports
+        .iter()
+        .map(|report| {
+            format!(
+                "{{\"day\":{},\"parse_us\":{},\"part_one\":\"{}\",\"part_one_us\":{},\"part_two\":\"{}\",\"part_two_us\":{}}}",
+                report.day,
+                report.parse.as_micros(),
+                escape(&report.part_one),
+                report.part_one_time.as_micros(),
+                escape(&report.part_two),
+                report.part_two_time.as_micros(),
+            )
+        })
+        .collect();
+    format!("[{}]", entries.join(","))
+}
+
+// Complain about every changed answer, and return whether there were any
+fn check(answers: &Answers, reports: &[Report]) -> bool {
+    let mismatches: Vec<Mismatch> = reports
+        .iter()
+        .flat_map(|report| answers.check(report))
+        .collect();
+    for report in reports {
+        if !answers.knows(report.day) {
+            eprintln!("No known answers for day {}", report.day);
+        }
+    }
+    for mismatch in &mismatches {
+        eprintln!("{mismatch}");
+    }
+    if mismatches.is_empty() {
+        eprintln!("All answers as expected");
+    } else {
+        eprintln!("{} answer(s) changed", mismatches.len());
+    }
+    mismatches.is_empty()
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            exit(2);
+        }
+    };
+    // Load these first, so a broken file doesn't waste a run
+    let answers = if options.check {
+        match Answers::load() {
+            Ok(answers) => Some(answers),
+            Err(message) => {
+                eprintln!("{message}");
+                exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    SimpleLogger::new()
+        .with_level(LevelFilter::Warn)
+        .env()
+        .init()
+        .unwrap();
+
+    let solutions: Vec<Box<dyn Runner>> = if options.days.is_empty() {
+        days::all()
+    } else {
+        let mut solutions = vec![];
+        for day in &options.days {
+            match days::get(*day) {
+                Some(solution) => solutions.push(solution),
+                None => {
+                    eprintln!("Day {day} isn't solved yet");
+                    exit(1);
+                }
+            }
+        }
+        solutions
+    };
+
+    let mut reports = vec![];
+    let mut missing = vec![];
+    for solution in solutions {
+        match read_input(&options, solution.day()) {
+            Ok(Some(input)) => reports.push(solution.run(&input)),
+            // Only complain if it was asked for by name
+            Ok(None) if options.days.is_empty() => missing.push(solution.day()),
+            Ok(None) => {
+                eprintln!("No input for day {}", solution.day());
+                exit(1);
+            }
+            Err(message) => {
+                eprintln!("{message}");
+                exit(1);
+            }
+        }
+    }
+
+    if options.json {
+        println!("{}", to_json(&reports));
+    } else {
+        print_table(&reports, &missing);
+    }
+    if let Some(answers) = answers {
+        if !check(&answers, &reports) {
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(str::to_string)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args("2 9 --json")).unwrap();
+        assert_eq!(options.days, vec![2, 9]);
+        assert!(options.json);
+        assert!(!options.check);
+        assert!(parse_args(args("--check")).unwrap().check);
+        let options = parse_args(args("9 --input other")).unwrap();
+        assert_eq!(options.input, Some("other".to_string()));
+        assert!(parse_args(args("all")).unwrap().days.is_empty());
+        assert!(parse_args(args("--stdin")).is_err());
+        assert!(parse_args(args("2 9 --input other")).is_err());
+        assert!(parse_args(args("nine")).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let report = Report {
+            day: 11,
+            parse: Duration::from_micros(5),
+            part_one: "2018".to_string(),
+            part_one_time: Duration::from_micros(1500),
+            part_two: "a \"b\"\n".to_string(),
+            part_two_time: Duration::from_millis(2),
+        };
+        assert_eq!(
+            to_json(&[report]),
+            r#"[{"day":11,"parse_us":5,"part_one":"2018","part_one_us":1500,"part_two":"a \"b\"\n","part_two_us":2000}]"#
+        );
+    }
+}
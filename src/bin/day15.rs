@@ -0,0 +1,6 @@
+use advent_of_code_2019::days::day15::Day15;
+use advent_of_code_2019::solution::run_main;
+
+fn main() {
+    run_main(&Day15 { pictures: true });
+}
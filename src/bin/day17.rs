@@ -0,0 +1,6 @@
+use advent_of_code_2019::days::day17::Day17;
+use advent_of_code_2019::solution::run_main;
+
+fn main() {
+    run_main(&Day17);
+}
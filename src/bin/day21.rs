@@ -0,0 +1,6 @@
+use advent_of_code_2019::days::day21::Day21;
+use advent_of_code_2019::solution::run_main;
+
+fn main() {
+    run_main(&Day21);
+}
@@ -0,0 +1,14 @@
+use advent_of_code_2019::days::day25::{interactive, Day25};
+use advent_of_code_2019::read_file;
+use advent_of_code_2019::solution::{input_path, run_main};
+use advent_of_code_2019::vm::parse_program;
+
+fn main() {
+    // Pass --interactive to play the game yourself instead
+    if std::env::args().any(|arg| arg == "--interactive") {
+        let program = parse_program(&read_file(&input_path(25))[0]);
+        interactive(&program);
+        return;
+    }
+    run_main(&Day25);
+}
@@ -0,0 +1,344 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::exit;
+use std::str::FromStr;
+
+use advent_of_code_2019::vm::{MAX_MEMORY, VM};
+
+/*
+ Run any Intcode program, feeding it input and showing its output.
+
+   intcode PROGRAM                         run it, printing each output on its own line
+   intcode PROGRAM --input 1               input from arguments (comma separated, or repeat it)
+   intcode PROGRAM --input-file PATH       ...from a file
+   intcode PROGRAM --stdin                 ...and from stdin, a line at a time as it's wanted
+   intcode PROGRAM --ascii                 input and output as text, as the ASCII capable days use
+   intcode PROGRAM --set 1=12 --set 2=2    change memory before running
+   intcode PROGRAM --max-steps 1000000     give up after that many instructions
+   intcode PROGRAM --peek 0                afterwards, show what's at an address (repeatable)
+   intcode PROGRAM --dump                  afterwards, show the whole of memory
+
+ Input comes from the arguments first, then the file, then stdin.  As ASCII, each --input is a
+ line of text, and files and stdin are passed through as they are.  Output that doesn't fit in
+ ASCII (usually an answer) is shown as a number on its own line.
+
+ Exits with 1 if the program fails, runs out of input or steps, after showing whatever it
+ managed.  So day 2 part one is:
+
+   intcode input/day2 --set 1=12 --set 2=2 --peek 0
+*/
+
+const USAGE: &str =
+    "Usage: intcode PROGRAM [--input VALUES]... [--input-file PATH] [--stdin] [--ascii] \
+[--set ADDRESS=VALUE]... [--max-steps N] [--peek ADDRESS]... [--dump]";
+
+#[derive(Debug, Default)]
+struct Options {
+    program: String,
+    inputs: Vec<String>,
+    input_file: Option<String>,
+    stdin: bool,
+    ascii: bool,
+    patches: Vec<(usize, isize)>,
+    max_steps: Option<usize>,
+    peeks: Vec<usize>,
+    dump: bool,
+}
+
+fn parse<T: FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{value} isn't a valid {what}"))
+}
+
+fn address(value: &str) -> Result<usize, String> {
+    match parse(value, "address")? {
+        address if address < MAX_MEMORY => Ok(address),
+        _ => Err(format!("{value} is beyond MAX_MEMORY")),
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut program = None;
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or(format!("{flag} needs a value"));
+        match arg.as_str() {
+            "--input" => options.inputs.push(value("--input")?),
+            "--input-file" => options.input_file = Some(value("--input-file")?),
+            "--stdin" => options.stdin = true,
+            "--ascii" => options.ascii = true,
+            "--set" => {
+                let patch = value("--set")?;
+                let Some((target, to)) = patch.split_once('=') else {
+                    return Err(format!("--set wants ADDRESS=VALUE, not {patch}"));
+                };
+                options
+                    .patches
+                    .push((address(target)?, parse(to, "value")?));
+            }
+            "--max-steps" => options.max_steps = Some(parse(&value("--max-steps")?, "count")?),
+            "--peek" => options.peeks.push(address(&value("--peek")?)?),
+            "--dump" => options.dump = true,
+            flag if flag.starts_with("--") => return Err(format!("Don't know {flag}")),
+            path if program.is_none() => program = Some(path.to_string()),
+            path => return Err(format!("Already running a program, so what's {path}?")),
+        }
+    }
+    options.program = program.ok_or("Which program?")?;
+    Ok(options)
+}
+
+// Numbers separated by commas or whitespace, or text as it is
+fn to_values(text: &str, ascii: bool) -> Result<Vec<isize>, String> {
+    if ascii {
+        return Ok(text.bytes().map(isize::from).collect());
+    }
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .map(|value| parse(value, "number"))
+        .collect()
+}
+
+// Everything from the arguments and the input file, in that order
+fn initial_input(options: &Options) -> Result<Vec<isize>, String> {
+    let mut input = vec![];
+    for line in &options.inputs {
+        if options.ascii {
+            input.extend(to_values(&format!("{line}\n"), true)?);
+        } else {
+            input.extend(to_values(line, false)?);
+        }
+    }
+    if let Some(path) = &options.input_file {
+        let text =
+            fs::read_to_string(path).map_err(|error| format!("Couldn't read {path}: {error}"))?;
+        input.extend(to_values(&text, options.ascii)?);
+    }
+    Ok(input)
+}
+
+fn write_output<W: Write>(vm: &mut VM, ascii: bool, out: &mut W) -> io::Result<()> {
+    while let Some(value) = vm.pop_front_output() {
+        match u8::try_from(value) {
+            Ok(byte) if ascii && byte.is_ascii() => write!(out, "{}", char::from(byte))?,
+            _ => writeln!(out, "{value}")?,
+        }
+    }
+    out.flush()
+}
+
+// Run until the VM stops by itself, counting steps against the budget if there is one.  Only
+// instructions that actually run count: an input instruction that finds nothing to read stops
+// the VM without running, and is charged when it runs for real, once there's more input.
+fn run_for(vm: &mut VM, max_steps: Option<usize>, steps: &mut usize) -> Result<(), String> {
+    let Some(max_steps) = max_steps else {
+        return vm.try_run().map_err(|error| error.to_string());
+    };
+    while *steps < max_steps {
+        let stopped = vm.try_run_for(1).map_err(|error| error.to_string())?;
+        if !vm.needs_input() {
+            *steps += 1;
+        }
+        if stopped {
+            return Ok(());
+        }
+    }
+    Err(format!("Gave up after {max_steps} instructions"))
+}
+
+// Run to the end, topping up input from stdin whenever the program waits for it
+fn execute<R: BufRead, W: Write>(
+    options: &Options,
+    vm: &mut VM,
+    stdin: &mut R,
+    out: &mut W,
+) -> Result<(), String> {
+    let mut steps = 0;
+    loop {
+        let ran = run_for(vm, options.max_steps, &mut steps);
+        write_output(vm, options.ascii, out).map_err(|error| error.to_string())?;
+        ran?;
+        if !vm.needs_input() {
+            return Ok(());
+        }
+        if !options.stdin {
+            return Err("The program wants more input than it was given".to_string());
+        }
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            Ok(0) => return Err("The program wants more input, but stdin has run out".to_string()),
+            Ok(_) => vm.queue_input(to_values(&line, options.ascii)?),
+            Err(error) => return Err(format!("Couldn't read stdin: {error}")),
+        }
+    }
+}
+
+// The addresses asked for, then all of memory as a program
+fn dump<W: Write>(options: &Options, vm: &VM, out: &mut W) -> io::Result<()> {
+    for address in &options.peeks {
+        // Memory nobody's touched yet is still 0
+        let value = vm.memory().get(*address).copied().unwrap_or(0);
+        writeln!(out, "{address}={value}")?;
+    }
+    if options.dump {
+        let memory: Vec<String> = vm.memory().iter().map(isize::to_string).collect();
+        writeln!(out, "{}", memory.join(","))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            exit(2);
+        }
+    };
+    let setup = fs::read_to_string(&options.program)
+        .map_err(|error| format!("Couldn't read {}: {error}", options.program))
+        .and_then(|text| to_values(&text, false))
+        .and_then(|program| Ok((program, initial_input(&options)?)));
+    let (program, input) = match setup {
+        Ok(setup) => setup,
+        Err(message) => {
+            eprintln!("{message}");
+            exit(1);
+        }
+    };
+
+    let mut vm = VM::new(program);
+    for (address, value) in &options.patches {
+        vm.set_memory(*address, *value);
+    }
+    vm.queue_input(input);
+
+    let mut out = io::stdout().lock();
+    let result = execute(&options, &mut vm, &mut io::stdin().lock(), &mut out);
+    dump(&options, &vm, &mut out).unwrap();
+    if let Err(message) = result {
+        eprintln!("{message}");
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(str::to_string)
+    }
+
+    // Run `program` as main would, with `stdin`, returning the output and how it ended
+    fn run(program: Vec<isize>, line: &str, stdin: &str) -> (String, Result<(), String>) {
+        let options = parse_args(args(line)).unwrap();
+        let mut vm = VM::new(program);
+        for (address, value) in &options.patches {
+            vm.set_memory(*address, *value);
+        }
+        vm.queue_input(initial_input(&options).unwrap());
+        let mut out = vec![];
+        let result = execute(&options, &mut vm, &mut stdin.as_bytes(), &mut out);
+        dump(&options, &vm, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), result)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(
+            "input/day2 --set 1=12 --set 2=2 --peek 0 --input 1,2 --input 3 --max-steps 10",
+        ))
+        .unwrap();
+        assert_eq!(options.program, "input/day2");
+        assert_eq!(options.patches, vec![(1, 12), (2, 2)]);
+        assert_eq!(options.peeks, vec![0]);
+        assert_eq!(options.inputs, vec!["1,2", "3"]);
+        assert_eq!(options.max_steps, Some(10));
+        assert!(!options.dump && !options.ascii && !options.stdin);
+
+        assert!(parse_args(args("--dump")).is_err());
+        assert!(parse_args(args("one two")).is_err());
+        assert!(parse_args(args("program --set 1")).is_err());
+        assert!(parse_args(args("program --set -1=2")).is_err());
+        assert!(parse_args(args("program --peek")).is_err());
+        assert!(parse_args(args("program --frobnicate")).is_err());
+    }
+
+    #[test]
+    fn test_patches_and_peek() {
+        // Day 2's example, which adds its way to 3500 at address 0
+        let program = vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+        let (out, result) = run(program.clone(), "p --peek 0 --peek 3 --peek 100", "");
+        assert_eq!(result, Ok(()));
+        assert_eq!(out, "0=3500\n3=70\n100=0\n");
+
+        let (out, _) = run(program, "p --set 9=1 --dump", "");
+        assert_eq!(out, "2050,9,10,41,2,3,11,0,99,1,40,50\n");
+    }
+
+    #[test]
+    fn test_input() {
+        // Outputs whatever it's given, twice over
+        let echo = vec![3, 0, 4, 0, 3, 0, 4, 0, 99];
+        assert_eq!(
+            run(echo.clone(), "p --input 5,6", ""),
+            ("5\n6\n".to_string(), Ok(()))
+        );
+        assert_eq!(
+            run(echo.clone(), "p --input 5 --stdin", "\n7\n").0,
+            "5\n7\n"
+        );
+        assert_eq!(run(echo.clone(), "p --input A --ascii", "").0, "A\n");
+        assert_eq!(
+            run(echo.clone(), "p --input 5", ""),
+            (
+                "5\n".to_string(),
+                Err("The program wants more input than it was given".to_string())
+            )
+        );
+        assert_eq!(
+            run(echo, "p --stdin", "5\n").1,
+            Err("The program wants more input, but stdin has run out".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ascii_output() {
+        // Prints "Hi", a newline, then 1000
+        let program = vec![104, 72, 104, 105, 104, 10, 104, 1000, 99];
+        assert_eq!(run(program.clone(), "p --ascii", "").0, "Hi\n1000\n");
+        assert_eq!(run(program, "p", "").0, "72\n105\n10\n1000\n");
+    }
+
+    #[test]
+    fn test_failures() {
+        // Jumps to itself forever
+        let (_, result) = run(vec![1105, 1, 0], "p --max-steps 10", "");
+        assert_eq!(result, Err("Gave up after 10 instructions".to_string()));
+        // Outputs, then hits a bad opcode, with the output still shown
+        let (out, result) = run(vec![104, 1, 42], "p", "");
+        assert_eq!(out, "1\n");
+        assert_eq!(result, Err("Invalid opcode 42 at 2".to_string()));
+        // Exactly enough steps is fine
+        assert_eq!(run(vec![104, 1, 99], "p --max-steps 2", "").1, Ok(()));
+    }
+
+    #[test]
+    fn test_max_steps_with_stdin() {
+        // Five instructions, stopping twice to wait for stdin, which costs nothing
+        let echo = vec![3, 0, 4, 0, 3, 0, 4, 0, 99];
+        assert_eq!(
+            run(echo.clone(), "p --stdin --max-steps 5", "5\n7\n"),
+            ("5\n7\n".to_string(), Ok(()))
+        );
+        assert_eq!(
+            run(echo, "p --stdin --max-steps 4", "5\n7\n"),
+            (
+                "5\n7\n".to_string(),
+                Err("Gave up after 4 instructions".to_string())
+            )
+        );
+    }
+}
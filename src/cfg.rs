@@ -0,0 +1,394 @@
+/*
+
+Control flow graph extraction for Intcode programs.
+
+Starting from one or more entry points, follow every path we can work out statically and split
+the reachable code into basic blocks.  Jumps whose target is an immediate operand get resolved.
+Jumps through memory (position mode) or the relative base can't be, without running the program,
+so they're recorded as Unresolved.  That's exactly how compiled Intcode returns from functions,
+so expect one of those at the end of most subroutines.
+
+The Intcode compilers also love `1105,1,x` (jump-if-true on a constant 1) as an unconditional
+jump, so conditions that are immediate values get folded.
+
+*/
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::disasm::{decode, Instruction, Param};
+use crate::vm::OC;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Target {
+    Resolved(usize),
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Terminator {
+    /// Block ends because the next instruction starts another block
+    Fallthrough(usize),
+    /// Jump that is always taken
+    Jump(Target),
+    /// Conditional jump.  `fallthrough` is where we go if it isn't taken.
+    Branch {
+        target: Target,
+        fallthrough: usize,
+    },
+    Halt,
+    /// Couldn't decode an instruction here: data, garbage, or ran off the end of memory
+    Invalid,
+}
+
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+impl BasicBlock {
+    /// First address after the block
+    #[must_use]
+    pub fn end(&self) -> usize {
+        self.instructions
+            .last()
+            .map_or(self.start, Instruction::next_address)
+    }
+
+    /// Statically known successor blocks
+    #[must_use]
+    pub fn successors(&self) -> Vec<usize> {
+        match self.terminator {
+            Terminator::Fallthrough(next) => vec![next],
+            Terminator::Jump(Target::Resolved(target)) => vec![target],
+            Terminator::Branch {
+                target: Target::Resolved(target),
+                fallthrough,
+            } => vec![target, fallthrough],
+            Terminator::Branch {
+                target: Target::Unresolved,
+                fallthrough,
+            } => vec![fallthrough],
+            Terminator::Jump(Target::Unresolved) | Terminator::Halt | Terminator::Invalid => {
+                vec![]
+            }
+        }
+    }
+
+    /// Whether control can leave the block to somewhere we couldn't work out
+    #[must_use]
+    pub fn has_unresolved_exit(&self) -> bool {
+        matches!(
+            self.terminator,
+            Terminator::Jump(Target::Unresolved)
+                | Terminator::Branch {
+                    target: Target::Unresolved,
+                    ..
+                }
+        )
+    }
+}
+
+/// What a single instruction does to control flow
+enum Flow {
+    Next,
+    Jump(Target),
+    Branch(Target),
+    Halt,
+}
+
+fn jump_target(param: &Param) -> Target {
+    match param {
+        Param::Immediate(target) if *target >= 0 => Target::Resolved(*target as usize),
+        _ => Target::Unresolved,
+    }
+}
+
+fn flow_of(instruction: &Instruction) -> Flow {
+    let jumps_when_non_zero = match instruction.opcode {
+        OC::JumpIfTrue => true,
+        OC::JumpIfFalse => false,
+        OC::End => return Flow::Halt,
+        _ => return Flow::Next,
+    };
+    let target = jump_target(&instruction.params[1]);
+    match instruction.params[0] {
+        Param::Immediate(condition) if (condition != 0) == jumps_when_non_zero => {
+            Flow::Jump(target)
+        }
+        // Never taken, so it's a (rather long) no-op
+        Param::Immediate(_) => Flow::Next,
+        _ => Flow::Branch(target),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub entries: Vec<usize>,
+    pub blocks: BTreeMap<usize, BasicBlock>,
+}
+
+impl Cfg {
+    /// Build the graph for a program that starts at address 0
+    #[must_use]
+    pub fn new(memory: &[isize]) -> Self {
+        Cfg::with_entries(memory, &[0])
+    }
+
+    /// Build the graph reachable from any of `entries`.  Useful when something else (e.g. call
+    /// detection) has found code that can only be reached through an unresolved jump.
+    #[must_use]
+    pub fn with_entries(memory: &[isize], entries: &[usize]) -> Self {
+        // First pass: find every reachable instruction, and every address that starts a block.
+        let mut instructions: BTreeMap<usize, Instruction> = BTreeMap::new();
+        let mut leaders: BTreeSet<usize> = entries.iter().copied().collect();
+        let mut to_visit: Vec<usize> = entries.to_vec();
+        while let Some(address) = to_visit.pop() {
+            if instructions.contains_key(&address) {
+                continue;
+            }
+            let Some(instruction) = decode(memory, address) else {
+                // Whatever is here will become an Invalid block
+                leaders.insert(address);
+                continue;
+            };
+            match flow_of(&instruction) {
+                Flow::Next => to_visit.push(instruction.next_address()),
+                Flow::Jump(target) => {
+                    if let Target::Resolved(target) = target {
+                        leaders.insert(target);
+                        to_visit.push(target);
+                    }
+                }
+                Flow::Branch(target) => {
+                    if let Target::Resolved(target) = target {
+                        leaders.insert(target);
+                        to_visit.push(target);
+                    }
+                    leaders.insert(instruction.next_address());
+                    to_visit.push(instruction.next_address());
+                }
+                Flow::Halt => {}
+            }
+            instructions.insert(address, instruction);
+        }
+
+        // Second pass: walk forwards from each leader until something ends the block.
+        let mut blocks = BTreeMap::new();
+        for &start in &leaders {
+            let mut block = BasicBlock {
+                start,
+                instructions: vec![],
+                terminator: Terminator::Invalid,
+            };
+            let mut address = start;
+            while let Some(instruction) = instructions.get(&address) {
+                block.instructions.push(instruction.clone());
+                address = instruction.next_address();
+                let terminator = match flow_of(instruction) {
+                    Flow::Next if leaders.contains(&address) => Terminator::Fallthrough(address),
+                    Flow::Next => continue,
+                    Flow::Jump(target) => Terminator::Jump(target),
+                    Flow::Branch(target) => Terminator::Branch {
+                        target,
+                        fallthrough: address,
+                    },
+                    Flow::Halt => Terminator::Halt,
+                };
+                block.terminator = terminator;
+                break;
+            }
+            blocks.insert(start, block);
+        }
+
+        Cfg {
+            entries: entries.to_vec(),
+            blocks,
+        }
+    }
+
+    /// The block whose instructions cover `address`, if any
+    #[must_use]
+    pub fn block_containing(&self, address: usize) -> Option<&BasicBlock> {
+        self.blocks
+            .range(..=address)
+            .rev()
+            .map(|(_, block)| block)
+            .find(|block| block.start == address || (block.start..block.end()).contains(&address))
+    }
+
+    /// Addresses of the jump instructions we couldn't resolve
+    #[must_use]
+    pub fn unresolved_jumps(&self) -> Vec<usize> {
+        self.blocks
+            .values()
+            .filter(|block| block.has_unresolved_exit())
+            .filter_map(|block| block.instructions.last().map(|i| i.address))
+            .collect()
+    }
+
+    /// Render as Graphviz DOT, e.g. `dot -Tsvg cfg.dot > cfg.svg`
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        let mut any_unresolved = false;
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            for instruction in &block.instructions {
+                let _ = write!(label, "{:>5}: {}\\l", instruction.address, instruction);
+            }
+            let style = match block.terminator {
+                Terminator::Invalid => ", style=dashed, color=red",
+                _ if self.entries.contains(&block.start) => ", style=bold",
+                _ => "",
+            };
+            if block.instructions.is_empty() {
+                let _ = write!(label, "{:>5}: ???\\l", block.start);
+            }
+            let _ = writeln!(dot, "    b{} [label=\"{}\"{}];", block.start, label, style);
+
+            match block.terminator {
+                Terminator::Fallthrough(next) => {
+                    let _ = writeln!(dot, "    b{} -> b{};", block.start, next);
+                }
+                Terminator::Jump(Target::Resolved(target)) => {
+                    let _ = writeln!(dot, "    b{} -> b{};", block.start, target);
+                }
+                Terminator::Branch {
+                    target: Target::Resolved(target),
+                    fallthrough,
+                } => {
+                    let _ = writeln!(
+                        dot,
+                        "    b{} -> b{} [label=\"taken\"];",
+                        block.start, target
+                    );
+                    let _ = writeln!(
+                        dot,
+                        "    b{} -> b{} [label=\"not taken\"];",
+                        block.start, fallthrough
+                    );
+                }
+                Terminator::Branch {
+                    target: Target::Unresolved,
+                    fallthrough,
+                } => {
+                    any_unresolved = true;
+                    let _ = writeln!(
+                        dot,
+                        "    b{} -> unresolved [label=\"taken\", style=dashed];",
+                        block.start
+                    );
+                    let _ = writeln!(
+                        dot,
+                        "    b{} -> b{} [label=\"not taken\"];",
+                        block.start, fallthrough
+                    );
+                }
+                Terminator::Jump(Target::Unresolved) => {
+                    any_unresolved = true;
+                    let _ = writeln!(dot, "    b{} -> unresolved [style=dashed];", block.start);
+                }
+                Terminator::Halt | Terminator::Invalid => {}
+            }
+        }
+        if any_unresolved {
+            dot.push_str("    unresolved [label=\"?\", shape=circle, style=dashed];\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_straight_line() {
+        // day 2 example: add, then halt
+        let cfg = Cfg::new(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+        assert_eq!(cfg.blocks.len(), 1);
+        let block = &cfg.blocks[&0];
+        assert_eq!(block.instructions.len(), 3);
+        assert_eq!(block.terminator, Terminator::Halt);
+        assert_eq!(block.end(), 9);
+    }
+
+    #[test]
+    fn test_quine_loop() {
+        // day 9 quine loops back to 0 with `1006,101,0` until it's printed itself
+        let cfg = Cfg::new(&[
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ]);
+        assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 15]);
+        assert_eq!(
+            cfg.blocks[&0].terminator,
+            Terminator::Branch {
+                target: Target::Resolved(0),
+                fallthrough: 15
+            }
+        );
+        assert_eq!(cfg.blocks[&15].terminator, Terminator::Halt);
+        assert!(cfg.unresolved_jumps().is_empty());
+    }
+
+    #[test]
+    fn test_constant_conditions_fold() {
+        // 1105,1,5 always jumps, 1106,1,0 never does
+        let cfg = Cfg::new(&[1105, 1, 5, 99, 99, 1106, 1, 0, 99]);
+        assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 5]);
+        assert_eq!(
+            cfg.blocks[&0].terminator,
+            Terminator::Jump(Target::Resolved(5))
+        );
+        assert_eq!(cfg.blocks[&5].instructions.len(), 2);
+        assert_eq!(cfg.blocks[&5].terminator, Terminator::Halt);
+    }
+
+    #[test]
+    fn test_indirect_jumps_unresolved() {
+        // Jump through memory, then a "return" through the relative base
+        let cfg = Cfg::new(&[5, 7, 8, 2106, 0, 0, 99, 1, 6]);
+        assert_eq!(
+            cfg.blocks[&0].terminator,
+            Terminator::Branch {
+                target: Target::Unresolved,
+                fallthrough: 3
+            }
+        );
+        assert_eq!(
+            cfg.blocks[&3].terminator,
+            Terminator::Jump(Target::Unresolved)
+        );
+        assert_eq!(cfg.unresolved_jumps(), vec![0, 3]);
+        assert!(cfg.to_dot().contains("b3 -> unresolved [style=dashed];"));
+    }
+
+    #[test]
+    fn test_invalid_target() {
+        let cfg = Cfg::new(&[1105, 1, 3, 42]);
+        assert_eq!(cfg.blocks[&3].terminator, Terminator::Invalid);
+        assert!(cfg.blocks[&3].instructions.is_empty());
+    }
+
+    #[test]
+    fn test_dot_output() {
+        let dot = Cfg::new(&[1105, 1, 4, 99, 1106, 0, 3]).to_dot();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("b0 [label=\"    0: JNZ 1, 4\\l\", style=bold];"));
+        assert!(dot.contains("b0 -> b4;"));
+        assert!(dot.contains("b4 -> b3;"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_block_containing() {
+        let cfg = Cfg::new(&[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]);
+        assert_eq!(cfg.block_containing(5).map(|b| b.start), Some(0));
+        assert!(cfg.block_containing(10).is_none());
+    }
+}
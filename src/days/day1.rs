@@ -0,0 +1,82 @@
+use log::debug;
+
+use crate::solution::Solution;
+
+fn calculate_fuel_needed(input: isize) -> isize {
+    input / 3 - 2
+}
+
+fn part_one(input: &[isize]) -> isize {
+    let answer = input
+        .iter()
+        .map(|x| calculate_fuel_needed(*x))
+        .collect::<Vec<isize>>();
+    debug!("{:?}", answer);
+    answer.iter().sum::<isize>()
+}
+
+fn part_two(input: &[isize]) -> isize {
+    let mut total_to_add: isize = 0;
+    for module in input {
+        let mut fuel_to_add = 0;
+        let mut to_add = *module;
+        loop {
+            to_add = calculate_fuel_needed(to_add);
+            if to_add <= 0 {
+                break;
+            }
+            fuel_to_add += to_add;
+        }
+        total_to_add += fuel_to_add;
+    }
+    total_to_add
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<isize>;
+    type One = isize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        1
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        input
+            .lines()
+            .map(|x| x.parse::<isize>().unwrap())
+            .collect::<Vec<isize>>()
+    }
+
+    fn part_one(&self, input: &Vec<isize>) -> isize {
+        part_one(input)
+    }
+
+    fn part_two(&self, input: &Vec<isize>) -> isize {
+        part_two(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::parse_input;
+
+    #[test]
+    fn test_calculate_fuel_needed() {
+        let test_cases: Vec<(isize, isize)> = vec![(12, 2), (14, 2), (1969, 654), (100756, 33583)];
+
+        for test_case in test_cases {
+            assert_eq!(calculate_fuel_needed(test_case.0), test_case.1);
+        }
+    }
+
+    #[test]
+    fn test_real_input() {
+        let input = parse_input(&Day1);
+        assert_eq!(Day1.part_one(&input), 3_402_609);
+        assert_eq!(Day1.part_two(&input), 5_101_025);
+    }
+}
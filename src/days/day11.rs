@@ -0,0 +1,328 @@
+use image::Rgb;
+use log::info;
+
+use crate::animation::Recorder;
+use crate::ocr::read_letters;
+use crate::render::Renderer;
+use crate::solution::Solution;
+use crate::terminal::{Colour, Screen, Style};
+use crate::vm::parse_program;
+use crate::vm::VM;
+use crate::{debug_println, Direction, Grid, Point, SparseGrid};
+
+/*
+ For this puzzle, need to "paint" an ID on the hull.  The hull is a 2D grid.
+ Panels start black.  The VM program input will tell us what to paint, and how to turn (90 degrees either left or right)
+ On each round the robot will:
+ * Tell the VM what colour the current panel is (0 black, 1 white)
+ * Read two output values.
+ ** First is what colour to paint the panel (0 black, 1 white)
+ ** Second is what way to turn 90 degrees (0 left, 1 right)
+ * Move forwards one cell
+*/
+
+// Paint Robot?  Paint Robot!
+struct PaintRobot {
+    location: Point<isize>,
+    heading: Direction,
+}
+
+impl PaintRobot {
+    // I should just have this as default, and make new take a starting Point
+    fn new() -> Self {
+        PaintRobot {
+            location: Point { x: 0, y: 0 },
+            heading: Direction::North,
+        }
+    }
+
+    fn turn(&mut self, direction: isize) {
+        match direction {
+            0 => {
+                debug_println!("Got {direction}. Turning Left");
+                self.heading = self.heading.turn_left()
+            }
+            1 => {
+                debug_println!("Got {direction}.  Turning Right");
+                self.heading = self.heading.turn_right()
+            }
+            _ => panic!("{direction} not valid!"),
+        }
+    }
+
+    fn move_robot(&mut self) {
+        self.location += self.heading.delta();
+        debug_println!(
+            "Location after moving {:?}: {:?}",
+            self.heading,
+            self.location
+        );
+    }
+}
+
+fn part_one(program: &[isize], pictures: bool) -> usize {
+    /*
+    Before you deploy the robot, you should probably have an estimate of the area it will cover:
+    specifically, you need to know the number of panels it paints at least once, regardless of color.
+    In the example above [on the puzzle page], the robot painted 6 panels at least once.
+    (It painted its starting panel twice, but that panel is still only counted once;
+    it also never painted the panel it ended on.)
+    */
+
+    // Using a HashSet, every time we paint, we'll add to the HashSet, as that'll dedupe.
+    // Final answer will be HashSet length.
+    let mut vm = VM::new(program.to_owned());
+    let mut robot = PaintRobot::new();
+
+    // Starting location is white
+    let mut map = SparseGrid::new().with_y_up(true);
+    map.set(robot.location, 0);
+
+    // Running the VM should see it end at a WaitingForInput state, which we can then build the loop around
+    vm.run();
+    while vm.needs_input() {
+        let current_colour: isize = match map.get(&robot.location) {
+            Some(colour) => *colour,
+            None => {
+                map.set(robot.location, 0);
+                0
+            } // Default to black
+        };
+
+        debug_println!("Pushing to Input: {current_colour}");
+        vm.push_input(current_colour);
+
+        // Then run, and it should give me two outputs
+        vm.run();
+
+        // First we paint (for part 1, really doesn't matter what)
+        if let Some(wanted_colour) = vm.pop_front_output() {
+            map.set(robot.location, wanted_colour);
+        } else {
+            panic!("Didn't get a paint output!")
+        }
+
+        // Then we turn
+        if let Some(turn) = vm.pop_front_output() {
+            robot.turn(turn);
+        } else {
+            panic!("Didn't get turn output!")
+        }
+
+        // Then we move
+        robot.move_robot();
+
+        // Then we run the robot, which should take us back to the start of the loop
+        vm.run();
+    }
+    if pictures {
+        info!("Saving image day_11_part_one.png");
+        Renderer::new()
+            .scale(8)
+            .save(&map, &palette, "day_11_part_one.png")
+            .unwrap();
+    }
+    map.len()
+}
+
+// Panels in black and white, with the robot (2) in red
+fn palette(colour: &isize) -> Rgb<u8> {
+    match colour {
+        0 => Rgb([0, 0, 0]),
+        1 => Rgb([255, 255, 255]),
+        2 => Rgb([255, 0, 0]),
+        _ => panic!("What? {colour}"),
+    }
+}
+
+// The same again, for the terminal
+fn style(colour: &isize) -> Style {
+    match colour {
+        0 => Style::new('.', Colour::Default),
+        1 => Style::new('#', Colour::White),
+        2 => Style::new('@', Colour::Red),
+        _ => panic!("What? {colour}"),
+    }
+}
+
+fn part_two(program: &[isize], pictures: bool, animate: bool, watch: bool) -> String {
+    /*
+    Based on the Space Law Space Brochure that the Space Police attached to one of your windows,
+    a valid registration identifier is always eight capital letters. After starting the robot on
+    a single white panel instead, what registration identifier does it paint on your hull?
+    */
+    let mut vm = VM::new(program.to_owned());
+    let mut robot = PaintRobot::new();
+    let mut recorder = Recorder::new(palette).y_up(true).scale(8).delay_ms(20);
+    let mut screen = Screen::new(style).y_up(true).fps(60);
+    // Starting location is white
+    let mut map = SparseGrid::new().with_y_up(true);
+    map.set(robot.location, 1);
+    // Running the VM should see it end at a WaitingForInput state, which we can then build the loop around
+    vm.run();
+    while vm.needs_input() {
+        let current_colour: isize = match map.get(&robot.location) {
+            Some(colour) => *colour,
+            None => {
+                map.set(robot.location, 0);
+                0
+            } // Default to black
+        };
+
+        debug_println!("Pushing to Input: {current_colour}");
+        vm.push_input(current_colour);
+
+        // Then run, and it should give me two outputs
+        vm.run();
+
+        // First we paint (for part 1, really doesn't matter what)
+        if let Some(wanted_colour) = vm.pop_front_output() {
+            map.set(robot.location, wanted_colour);
+        } else {
+            panic!("Didn't get a paint output!")
+        }
+
+        // Then we turn
+        if let Some(turn) = vm.pop_front_output() {
+            robot.turn(turn);
+        } else {
+            panic!("Didn't get turn output!")
+        }
+
+        // Then we move
+        robot.move_robot();
+
+        if animate {
+            recorder.capture(map.cells().iter().chain([(&robot.location, &2)]));
+        }
+        if watch {
+            screen
+                .draw(map.cells().iter().chain([(&robot.location, &2)]))
+                .unwrap();
+        }
+
+        // Then we run the robot, which should take us back to the start of the loop
+        vm.run();
+    }
+    if watch {
+        screen.finish().unwrap();
+    }
+    // The identifier, written out in white panels
+    let painted = map.render(|colour| if colour == Some(&1) { '#' } else { ' ' });
+    info!("Painted:\n{painted}");
+    if pictures {
+        info!("Saving image day_11_part_two.png");
+        Renderer::new()
+            .scale(8)
+            .save(&map, &palette, "day_11_part_two.png")
+            .unwrap();
+    }
+    if animate {
+        info!("Saving animation of {} frames", recorder.frame_count());
+        recorder.save_gif("day_11_part_two.gif").unwrap();
+    }
+    read_letters(&painted)
+}
+
+#[derive(Default)]
+pub struct Day11 {
+    /// Save pictures of the hull
+    pub pictures: bool,
+    /// Also save a GIF of the robot at work
+    pub animate: bool,
+    /// Show the robot at work in the terminal
+    pub watch: bool,
+}
+
+impl Solution for Day11 {
+    type Input = Vec<isize>;
+    type One = usize;
+    type Two = String;
+
+    fn day(&self) -> u32 {
+        11
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> usize {
+        part_one(program, self.pictures)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> String {
+        part_two(program, self.pictures, self.animate, self.watch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::parse_input;
+    use rstest::*;
+
+    #[test]
+    fn test_facing_north() {
+        let robot = PaintRobot::new();
+        // New robots should be facing north
+        assert_eq!(robot.heading, Direction::North);
+    }
+
+    #[rstest]
+    #[case(Direction::North, Direction::East)]
+    #[case(Direction::East, Direction::South)]
+    #[case(Direction::South, Direction::West)]
+    #[case(Direction::West, Direction::North)]
+    fn test_turn_right(#[case] start_heading: Direction, #[case] expected_location: Direction) {
+        let mut robot = PaintRobot::new();
+        robot.heading = start_heading;
+        robot.turn(1);
+        assert_eq!(robot.heading, expected_location);
+    }
+
+    #[rstest]
+    #[case(Direction::North, Direction::West, Point{ x: -1, y: 0})]
+    #[case(Direction::East, Direction::North, Point{ x: 0, y: 1})]
+    #[case(Direction::South, Direction::East, Point{ x: 1, y: 0})]
+    #[case(Direction::West, Direction::South, Point{ x: 0, y: -1})]
+    fn turn_left_and_move(
+        #[case] start_heading: Direction,
+        #[case] expected_heading: Direction,
+        #[case] expected_location: Point<isize>,
+    ) {
+        let mut robot = PaintRobot::new();
+        robot.heading = start_heading;
+        robot.turn(0);
+        robot.move_robot();
+        assert_eq!(robot.heading, expected_heading);
+        assert_eq!(robot.location, expected_location);
+    }
+
+    #[rstest]
+    #[case(Direction::North, Direction::East, Point{ x: 1, y: 0})]
+    #[case(Direction::East, Direction::South, Point{ x: 0, y: -1})]
+    #[case(Direction::South, Direction::West, Point{ x: -1, y: 0})]
+    #[case(Direction::West, Direction::North, Point{ x: 0, y: 1})]
+    fn turn_right_and_move(
+        #[case] start_heading: Direction,
+        #[case] expected_heading: Direction,
+        #[case] expected_location: Point<isize>,
+    ) {
+        let mut robot = PaintRobot::new();
+        robot.heading = start_heading;
+        robot.turn(1);
+        robot.move_robot();
+        assert_eq!(robot.heading, expected_heading);
+        assert_eq!(robot.location, expected_location);
+    }
+
+    #[test]
+    fn test_real_input() {
+        // Default options, so no pictures
+        let day = Day11::default();
+        let program = parse_input(&day);
+        assert_eq!(day.part_one(&program), 2088);
+        assert_eq!(day.part_two(&program), "URCAFLCP");
+    }
+}
@@ -0,0 +1,368 @@
+use image::Rgb;
+use log::{info, trace};
+
+use crate::animation::Recorder;
+use crate::render::{Palette, Renderer};
+use crate::solution::Solution;
+use crate::terminal::{Colour, Screen, Style};
+use crate::vm::parse_program;
+use crate::vm::VM;
+use crate::{get_user_input, Grid, Point, SparseGrid};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Empty,
+    Wall,
+    Block,
+    HPaddle,
+    Ball,
+}
+
+impl TryFrom<isize> for Tile {
+    type Error = String;
+
+    fn try_from(number: isize) -> Result<Self, Self::Error> {
+        match number {
+            0 => Ok(Tile::Empty),
+            1 => Ok(Tile::Wall),
+            2 => Ok(Tile::Block),
+            3 => Ok(Tile::HPaddle),
+            4 => Ok(Tile::Ball),
+            _ => Err(format!("Unknown tile type: {number}")),
+        }
+    }
+}
+
+impl Tile {
+    fn glyph(&self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::HPaddle => '_',
+            Tile::Ball => 'o',
+        }
+    }
+
+    fn style(&self) -> Style {
+        let colour = match self {
+            Tile::Empty => Colour::Default,
+            Tile::Wall => Colour::White,
+            Tile::Block => Colour::Red,
+            Tile::HPaddle => Colour::Green,
+            Tile::Ball => Colour::Blue,
+        };
+        Style::new(self.glyph(), colour)
+    }
+
+    fn colour(&self) -> Rgb<u8> {
+        match self {
+            Tile::Empty => Rgb([0, 0, 0]),
+            Tile::Wall => Rgb([255, 255, 255]),
+            Tile::Block => Rgb([255, 0, 0]),
+            Tile::HPaddle => Rgb([0, 255, 0]),
+            Tile::Ball => Rgb([0, 0, 255]),
+        }
+    }
+}
+
+/*
+ The game's state, built up from the VM's output as it goes.  Output comes in threes: x, y and a
+ tile to draw there, except that x = -1, y = 0 means the third value is the new score.
+*/
+#[derive(Debug, Default)]
+struct Game {
+    board: SparseGrid<Tile>,
+    score: isize,
+    ball: Option<Point<isize>>,
+    paddle: Option<Point<isize>>,
+}
+
+impl Game {
+    fn update(&mut self, x: isize, y: isize, value: isize) {
+        if (x, y) == (-1, 0) {
+            trace!("Score: {value}");
+            self.score = value;
+            return;
+        }
+        let point = Point { x, y };
+        let tile = Tile::try_from(value).unwrap_or_else(|error| panic!("{error}"));
+        match tile {
+            Tile::Ball => self.ball = Some(point),
+            Tile::HPaddle => self.paddle = Some(point),
+            _ => {}
+        }
+        self.board.set(point, tile);
+    }
+
+    // Take everything the VM has output so far
+    fn read_output(&mut self, vm: &mut VM) {
+        while vm.has_output() {
+            let (Some(x), Some(y), Some(value)) = (
+                vm.pop_front_output(),
+                vm.pop_front_output(),
+                vm.pop_front_output(),
+            ) else {
+                panic!("Output didn't come in threes");
+            };
+            self.update(x, y, value);
+        }
+    }
+
+    fn blocks(&self) -> usize {
+        self.board
+            .values()
+            .filter(|tile| **tile == Tile::Block)
+            .count()
+    }
+
+    fn render(&self) -> String {
+        // The board's drawn from 0,0, even if the game hasn't drawn there yet
+        let max = self
+            .board
+            .bounds()
+            .map_or(Point { x: 0, y: 0 }, |bounds| bounds.max);
+        let mut text = format!("Score: {}\n", self.score);
+        for y in 0..=max.y {
+            for x in 0..=max.x {
+                let tile = self.board.get(&Point { x, y }).unwrap_or(&Tile::Empty);
+                text.push(tile.glyph());
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// Joystick: -1 left, 0 neutral, 1 right.  Keeping the paddle under the ball never misses.
+fn autopilot(game: &Game) -> isize {
+    match (&game.ball, &game.paddle) {
+        (Some(ball), Some(paddle)) => (ball.x - paddle.x).signum(),
+        _ => 0,
+    }
+}
+
+// Keys a, s and d (then enter) for left, neutral and right
+fn keyboard(game: &Game) -> isize {
+    println!("{}", game.render());
+    loop {
+        match get_user_input("a: left, s: stay, d: right").trim() {
+            "a" => return -1,
+            "s" | "" => return 0,
+            "d" => return 1,
+            other => println!("Don't know {other:?}"),
+        }
+    }
+}
+
+// Play until the game ends, asking `joystick` what to do every time the game wants input
+fn play<F: FnMut(&Game) -> isize>(vm: &mut VM, game: &mut Game, mut joystick: F) {
+    loop {
+        vm.run();
+        game.read_output(vm);
+        if !vm.needs_input() {
+            break;
+        }
+        vm.push_input(joystick(game));
+    }
+}
+
+// Tile colours, with a legend for the picture
+struct Colours;
+
+impl Palette<Tile> for Colours {
+    fn colour(&self, tile: &Tile) -> Rgb<u8> {
+        tile.colour()
+    }
+
+    fn legend(&self) -> Vec<(String, Rgb<u8>)> {
+        [
+            (Tile::Wall, "Wall"),
+            (Tile::Block, "Block"),
+            (Tile::HPaddle, "Paddle"),
+            (Tile::Ball, "Ball"),
+        ]
+        .iter()
+        .map(|(tile, label)| ((*label).to_owned(), tile.colour()))
+        .collect()
+    }
+}
+
+fn make_image_of_board(board: &SparseGrid<Tile>, name: &str) {
+    let image_start = std::time::Instant::now();
+    info!("Saving image {name}");
+    Renderer::new()
+        .scale(16)
+        .legend(true)
+        .save(board, &Colours, name)
+        .unwrap();
+    info!("{} image creation took: {:?}", name, image_start.elapsed());
+}
+
+fn part_one(program: &[isize]) -> usize {
+    let mut vm = VM::new(program.to_owned());
+    vm.run();
+    let mut game = Game::default();
+    game.read_output(&mut vm);
+    game.blocks()
+}
+
+fn part_two(
+    program: &[isize],
+    pictures: bool,
+    interactive: bool,
+    animate: bool,
+    watch: bool,
+) -> isize {
+    /*
+    Memory address 0 represents the number of quarters that have been inserted; set it to 2 to
+    play for free.  Beat the game by breaking all the blocks. What is your score after the last
+    block is broken?
+    */
+    let mut vm = VM::new(program.to_owned());
+    vm.set_memory(0, 2);
+    let mut game = Game::default();
+    let joystick = if interactive { keyboard } else { autopilot };
+    // The game asks for input once per tick, so that's when to take a frame.  There are a lot
+    // of ticks, so only keep some of them.
+    let mut recorder = Recorder::new(Tile::colour).scale(8).every(4).delay_ms(20);
+    let mut screen = Screen::new(Tile::style).fps(120);
+    play(&mut vm, &mut game, |game| {
+        if animate {
+            recorder.capture_map(game.board.cells());
+        }
+        if watch {
+            screen.set_caption(&format!("Score: {}", game.score));
+            screen.draw_map(game.board.cells()).unwrap();
+        }
+        joystick(game)
+    });
+    if watch {
+        screen.finish().unwrap();
+    }
+    if interactive {
+        println!("{}", game.render());
+    }
+    if pictures {
+        make_image_of_board(&game.board, "part_two.png");
+    }
+    if animate {
+        info!("Saving animation of {} frames", recorder.frame_count());
+        recorder.save_gif("day_13_part_two.gif").unwrap();
+    }
+    if game.blocks() > 0 {
+        info!("Game over with {} blocks left", game.blocks());
+    }
+    game.score
+}
+
+#[derive(Default)]
+pub struct Day13 {
+    /// Save a picture of the board at the end of part two
+    pub pictures: bool,
+    /// Play part two yourself
+    pub interactive: bool,
+    /// Also save a GIF of the game
+    pub animate: bool,
+    /// Show the game in the terminal
+    pub watch: bool,
+}
+
+impl Solution for Day13 {
+    type Input = Vec<isize>;
+    type One = usize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        13
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> usize {
+        part_one(program)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(
+            program,
+            self.pictures,
+            self.interactive,
+            self.animate,
+            self.watch,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::parse_input;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0, Ok(Tile::Empty))]
+    #[case(4, Ok(Tile::Ball))]
+    #[case(5, Err("Unknown tile type: 5".to_string()))]
+    fn test_tile(#[case] number: isize, #[case] expected: Result<Tile, String>) {
+        assert_eq!(Tile::try_from(number), expected);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut game = Game::default();
+        game.update(1, 2, 3);
+        game.update(6, 5, 4);
+        game.update(2, 0, 2);
+        game.update(-1, 0, 12345);
+        assert_eq!(game.paddle, Some(Point { x: 1, y: 2 }));
+        assert_eq!(game.ball, Some(Point { x: 6, y: 5 }));
+        assert_eq!(game.blocks(), 1);
+        assert_eq!(game.score, 12345);
+        // Breaking a block draws an empty tile over it
+        game.update(2, 0, 0);
+        assert_eq!(game.blocks(), 0);
+    }
+
+    #[rstest]
+    #[case(3, 5, -1)]
+    #[case(5, 5, 0)]
+    #[case(7, 5, 1)]
+    fn test_autopilot(#[case] ball: isize, #[case] paddle: isize, #[case] expected: isize) {
+        let mut game = Game::default();
+        game.update(ball, 3, 4);
+        game.update(paddle, 4, 3);
+        assert_eq!(autopilot(&game), expected);
+    }
+
+    #[test]
+    fn test_play() {
+        // Draws a paddle and a ball, reads the joystick once, and scores 10 more than it
+        let program = vec![
+            104, 1, 104, 2, 104, 3, 104, 3, 104, 1, 104, 4, 3, 30, 104, -1, 104, 0, 1001, 30, 10,
+            31, 4, 31, 99,
+        ];
+        let mut vm = VM::new(program);
+        let mut game = Game::default();
+        play(&mut vm, &mut game, autopilot);
+        assert!(vm.finished());
+        assert_eq!(game.score, 11);
+        assert_eq!(game.render(), "Score: 11\n    \n   o\n _  \n");
+    }
+
+    #[test]
+    fn test_real_input() {
+        let program = parse_input(&Day13::default());
+        assert_eq!(Day13::default().part_one(&program), 268);
+    }
+
+    #[test]
+    fn test_real_input_part_two() {
+        // Default options, so no pictures
+        let day = Day13::default();
+        let program = parse_input(&day);
+        assert_eq!(day.part_two(&program), 13989);
+    }
+}
@@ -0,0 +1,380 @@
+use std::cmp::max;
+use std::collections::{HashMap, VecDeque};
+
+use image::Rgb;
+use log::info;
+
+use crate::render::{Palette, Renderer};
+use crate::solution::Solution;
+use crate::vm::{parse_program, VM};
+use crate::{debug_println, Direction, Grid, Point, SparseGrid};
+
+/*
+ A repair droid, controlled by the VM, is somewhere in a maze it can't see.  Each round:
+ * We give it a movement command (1 north, 2 south, 3 west, 4 east)
+ * It outputs a status
+ ** 0: hit a wall, didn't move
+ ** 1: moved
+ ** 2: moved, and is now on the oxygen system
+
+ Rather than walking the droid back and forth, we clone the whole VM at every cell we reach.
+ Each clone *is* a droid standing on that cell, so a plain BFS over clones maps the maze, and
+ the first time a clone finds oxygen, that's the shortest route there.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    HitWall,
+    Moved,
+    FoundOxygen,
+}
+
+impl TryFrom<isize> for Status {
+    type Error = String;
+
+    fn try_from(number: isize) -> Result<Self, Self::Error> {
+        match number {
+            0 => Ok(Status::HitWall),
+            1 => Ok(Status::Moved),
+            2 => Ok(Status::FoundOxygen),
+            _ => Err(format!("Unknown status: {number}")),
+        }
+    }
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+fn command(direction: &Direction) -> isize {
+    match direction {
+        Direction::North => 1,
+        Direction::South => 2,
+        Direction::West => 3,
+        Direction::East => 4,
+        _ => panic!("The droid can't move {direction:?}"),
+    }
+}
+
+// Anything that can be told to move and says what happened.  Cloning has to give an independent
+// droid in the same place, which is what lets the explorer fork at each cell.
+trait Droid: Clone {
+    fn travel(&mut self, direction: &Direction) -> Status;
+}
+
+impl Droid for VM {
+    fn travel(&mut self, direction: &Direction) -> Status {
+        self.push_input(command(direction));
+        self.run();
+        match self.pop_front_output().map(Status::try_from) {
+            Some(Ok(status)) => status,
+            Some(Err(error)) => panic!("{error}"),
+            None => panic!("Droid didn't report a status"),
+        }
+    }
+}
+
+/// The whole maze, mapped once while parsing, as both parts want all of it
+#[derive(Debug)]
+pub struct Exploration {
+    // North is +y, same as day 11
+    map: SparseGrid<Tile>,
+    oxygen: Option<Point<isize>>,
+    // Fewest moves from the start to the oxygen system
+    oxygen_distance: Option<usize>,
+}
+
+fn explore<D: Droid>(droid: D) -> Exploration {
+    let start = Point { x: 0, y: 0 };
+    let mut map = SparseGrid::new().with_y_up(true);
+    map.set(start, Tile::Open);
+    let mut oxygen = None;
+    let mut oxygen_distance = None;
+
+    let mut frontier = VecDeque::from([(start, droid, 0)]);
+    while let Some((location, droid, distance)) = frontier.pop_front() {
+        for direction in &DIRECTIONS {
+            let next = map.step(&location, direction);
+            if map.contains(&next) {
+                continue;
+            }
+            let mut moved = droid.clone();
+            let status = moved.travel(direction);
+            debug_println!("{location:?} -> {direction:?}: {status:?}");
+            match status {
+                Status::HitWall => {
+                    map.set(next, Tile::Wall);
+                }
+                Status::Moved => {
+                    map.set(next, Tile::Open);
+                    frontier.push_back((next, moved, distance + 1));
+                }
+                Status::FoundOxygen => {
+                    map.set(next, Tile::Oxygen);
+                    oxygen = Some(next);
+                    oxygen_distance = Some(distance + 1);
+                    frontier.push_back((next, moved, distance + 1));
+                }
+            }
+        }
+    }
+    Exploration {
+        map,
+        oxygen,
+        oxygen_distance,
+    }
+}
+
+// Minutes for oxygen to spread from `source` into every open cell reachable from it
+fn fill_time(map: &SparseGrid<Tile>, source: &Point<isize>) -> usize {
+    let mut filled = HashMap::from([(*source, 0)]);
+    let mut queue = VecDeque::from([*source]);
+    let mut longest = 0;
+    while let Some(location) = queue.pop_front() {
+        let minutes = filled[&location];
+        longest = max(longest, minutes);
+        for direction in &DIRECTIONS {
+            let next = map.step(&location, direction);
+            let open = matches!(map.get(&next), Some(Tile::Open | Tile::Oxygen));
+            if open && !filled.contains_key(&next) {
+                filled.insert(next, minutes + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    longest
+}
+
+// Tile colours, with a legend for the picture.  Anything never explored stays black.
+struct Colours;
+
+impl Palette<Tile> for Colours {
+    fn colour(&self, tile: &Tile) -> Rgb<u8> {
+        match tile {
+            Tile::Wall => Rgb([128, 128, 128]),
+            Tile::Open => Rgb([255, 255, 255]),
+            Tile::Oxygen => Rgb([0, 0, 255]),
+        }
+    }
+
+    fn legend(&self) -> Vec<(String, Rgb<u8>)> {
+        [
+            (Tile::Wall, "Wall"),
+            (Tile::Open, "Open"),
+            (Tile::Oxygen, "Oxygen"),
+        ]
+        .iter()
+        .map(|(tile, label)| ((*label).to_owned(), self.colour(tile)))
+        .collect()
+    }
+}
+
+fn part_one(exploration: &Exploration, pictures: bool) -> usize {
+    /*
+    What is the fewest number of movement commands required to move the repair droid from its
+    starting position to the location of the oxygen system?
+    */
+    if pictures {
+        info!("Saving image day_15.png");
+        Renderer::new()
+            .scale(8)
+            .legend(true)
+            .save(&exploration.map, &Colours, "day_15.png")
+            .unwrap();
+    }
+    match exploration.oxygen_distance {
+        Some(distance) => distance,
+        None => panic!("Explored everywhere and didn't find the oxygen system"),
+    }
+}
+
+fn part_two(exploration: &Exploration) -> usize {
+    /*
+    Use the repair droid to get a complete map of the area. How many minutes will it take to
+    fill with oxygen?
+    */
+    match &exploration.oxygen {
+        Some(oxygen) => fill_time(&exploration.map, oxygen),
+        None => panic!("Explored everywhere and didn't find the oxygen system"),
+    }
+}
+
+#[derive(Default)]
+pub struct Day15 {
+    /// Save a picture of the maze
+    pub pictures: bool,
+}
+
+impl Solution for Day15 {
+    type Input = Exploration;
+    type One = usize;
+    type Two = usize;
+
+    fn day(&self) -> u32 {
+        15
+    }
+
+    // Exploring is most of the work, and the same for both parts
+    fn parse(&self, input: &str) -> Exploration {
+        // Every step forks the droid, and each fork only writes a little
+        let mut droid = VM::new(parse_program(input));
+        droid.share_memory();
+        explore(droid)
+    }
+
+    fn part_one(&self, exploration: &Exploration) -> usize {
+        part_one(exploration, self.pictures)
+    }
+
+    fn part_two(&self, exploration: &Exploration) -> usize {
+        part_two(exploration)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+    use std::rc::Rc;
+
+    // Stands in for the Intcode droid, walking a maze drawn in text.  The top row is north.
+    #[derive(Clone)]
+    struct MazeDroid {
+        maze: Rc<Vec<Vec<char>>>,
+        location: Point<isize>,
+    }
+
+    impl MazeDroid {
+        fn new(maze: &[&str]) -> Self {
+            let maze: Vec<Vec<char>> = maze.iter().map(|line| line.chars().collect()).collect();
+            MazeDroid {
+                maze: Rc::new(maze),
+                location: Point { x: 0, y: 0 },
+            }
+        }
+
+        // The droid starts at 'D', which is (0, 0).  Off the edge of the drawing counts as wall.
+        fn cell(&self, location: &Point<isize>) -> char {
+            let (row, column) = self
+                .maze
+                .iter()
+                .enumerate()
+                .find_map(|(row, line)| line.iter().position(|c| *c == 'D').map(|c| (row, c)))
+                .unwrap();
+            let row = row as isize - location.y;
+            let column = column as isize + location.x;
+            usize::try_from(row)
+                .ok()
+                .and_then(|row| self.maze.get(row))
+                .and_then(|line| line.get(usize::try_from(column).ok()?))
+                .copied()
+                .unwrap_or('#')
+        }
+    }
+
+    impl Droid for MazeDroid {
+        fn travel(&mut self, direction: &Direction) -> Status {
+            let next = self.location + direction.delta();
+            match self.cell(&next) {
+                '#' => Status::HitWall,
+                'O' => {
+                    self.location = next;
+                    Status::FoundOxygen
+                }
+                _ => {
+                    self.location = next;
+                    Status::Moved
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(0, Ok(Status::HitWall))]
+    #[case(2, Ok(Status::FoundOxygen))]
+    #[case(3, Err("Unknown status: 3".to_string()))]
+    fn test_status(#[case] number: isize, #[case] expected: Result<Status, String>) {
+        assert_eq!(Status::try_from(number), expected);
+    }
+
+    #[rstest]
+    #[case(Direction::North, Point{ x: 0, y: 1})]
+    #[case(Direction::South, Point{ x: 0, y: -1})]
+    #[case(Direction::East, Point{ x: 1, y: 0})]
+    #[case(Direction::West, Point{ x: -1, y: 0})]
+    fn test_north_is_up(#[case] direction: Direction, #[case] expected: Point<isize>) {
+        let exploration = explore(MazeDroid::new(&["D"]));
+        assert_eq!(
+            exploration.map.step(&Point { x: 0, y: 0 }, &direction),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_explore_example() {
+        // The example from the puzzle page
+        let droid = MazeDroid::new(&[" ##", "#..#", "#D.#", " #O"]);
+        let exploration = explore(droid);
+        assert_eq!(exploration.oxygen, Some(Point { x: 1, y: -1 }));
+        assert_eq!(exploration.oxygen_distance, Some(2));
+        assert_eq!(
+            exploration.map.get(&Point { x: 0, y: 1 }),
+            Some(&Tile::Open)
+        );
+        assert_eq!(
+            exploration.map.get(&Point { x: -1, y: 0 }),
+            Some(&Tile::Wall)
+        );
+        // 5 open cells (one of them oxygen) and the 9 walls around them
+        assert_eq!(exploration.map.len(), 14);
+    }
+
+    #[test]
+    fn test_parts_share_exploration() {
+        // The same example, explored once for both parts
+        let exploration = explore(MazeDroid::new(&[" ##", "#..#", "#D.#", " #O"]));
+        assert_eq!(part_one(&exploration, false), 2);
+        assert_eq!(part_two(&exploration), 3);
+    }
+
+    #[test]
+    fn test_explore_takes_shortest_route() {
+        // The long way round is 10 moves, straight down is 4
+        let droid = MazeDroid::new(&[
+            "#######", "#D....#", "#.###.#", "#.#...#", "#.#.###", "#O....#", "#######",
+        ]);
+        let exploration = explore(droid);
+        assert_eq!(exploration.oxygen_distance, Some(4));
+    }
+
+    #[test]
+    fn test_explore_with_vm() {
+        // A droid boxed in on all sides: read a command, report a wall, repeat
+        let program = vec![3, 7, 104, 0, 1105, 1, 0, 0];
+        let exploration = explore(VM::new(program));
+        assert_eq!(exploration.map.len(), 5);
+        assert_eq!(exploration.oxygen, None);
+    }
+
+    #[test]
+    fn test_fill_time_example() {
+        // Part two's example: oxygen spreads from O and takes 4 minutes to fill the area
+        let map = SparseGrid::parse(" ##\n#..##\n#.#..#\n#.O.#\n ###", |c| match c {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Open),
+            'O' => Some(Tile::Oxygen),
+            _ => None,
+        });
+        assert_eq!(fill_time(&map, &Point { x: 2, y: 3 }), 4);
+    }
+}
@@ -0,0 +1,425 @@
+use std::fmt;
+
+use log::info;
+
+use crate::ascii::{queue_line, take_output};
+use crate::solution::Solution;
+use crate::vm::{parse_program, VM};
+use crate::{debug_println, DenseGrid, Direction, Grid, Point, SparseGrid};
+
+/*
+ The VM is an ASCII program showing a camera view of scaffolding, with a vacuum robot on it:
+ * '#' is scaffold, '.' is open space
+ * '^', 'v', '<' or '>' is the robot (on scaffold), facing that way
+
+ Unlike days 11 and 15 these are screen coordinates: x is the column, y is the row, so y grows
+ going south.
+*/
+
+#[derive(Debug)]
+struct Scaffold {
+    // Whether each cell is scaffold.  The grid knows North is -y.
+    cells: DenseGrid<bool>,
+    robot: Point<isize>,
+    heading: Direction,
+}
+
+impl Scaffold {
+    fn contains(&self, point: &Point<isize>) -> bool {
+        self.cells.get(point) == Some(&true)
+    }
+}
+
+// Which way the robot's facing, if that's the robot
+fn robot_heading(c: char) -> Option<Direction> {
+    match c {
+        '^' => Some(Direction::North),
+        'v' => Some(Direction::South),
+        '<' => Some(Direction::West),
+        '>' => Some(Direction::East),
+        'X' => panic!("Robot has fallen off the scaffold"),
+        _ => None,
+    }
+}
+
+fn parse_view(view: &str) -> Scaffold {
+    // The camera finishes with a blank line, which isn't part of the picture
+    let view = view.trim_end();
+    // The robot's always standing on scaffold
+    let cells = DenseGrid::parse(view, |c| c != '.');
+    let robot = SparseGrid::parse(view, robot_heading)
+        .iter()
+        .next()
+        .map(|(point, heading)| (point, *heading));
+    match robot {
+        Some((robot, heading)) => Scaffold {
+            cells,
+            robot,
+            heading,
+        },
+        None => panic!("No robot in the camera view"),
+    }
+}
+
+// Scaffold with scaffold on all four sides, in reading order
+fn intersections(scaffold: &Scaffold) -> Vec<Point<isize>> {
+    scaffold
+        .cells
+        .iter()
+        .filter(|(point, cell)| {
+            let neighbours = scaffold.cells.neighbours(point);
+            **cell && neighbours.iter().filter(|(_, _, next)| **next).count() == 4
+        })
+        .map(|(point, _)| point)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Turn {
+    Left,
+    Right,
+}
+
+// Turn, then go forwards `steps` cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    turn: Turn,
+    steps: usize,
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.turn {
+            Turn::Left => write!(f, "L,{}", self.steps),
+            Turn::Right => write!(f, "R,{}", self.steps),
+        }
+    }
+}
+
+fn encode(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// Follow the scaffold from the robot to the end, going straight over every intersection.  This
+// assumes the robot needs to turn before its first move, which it always does in the puzzle.
+fn find_path(scaffold: &Scaffold) -> Vec<Segment> {
+    let mut location = scaffold.robot;
+    let mut heading = scaffold.heading;
+    let ahead = |location: &Point<isize>, heading: &Direction| {
+        let next = scaffold.cells.step(location, heading);
+        scaffold.contains(&next).then_some(next)
+    };
+    let mut path = vec![];
+    loop {
+        let left = heading.turn_left();
+        let right = heading.turn_right();
+        let (turn, new_heading) = if ahead(&location, &left).is_some() {
+            (Turn::Left, left)
+        } else if ahead(&location, &right).is_some() {
+            (Turn::Right, right)
+        } else {
+            // Dead end, so we've covered everything
+            break;
+        };
+        heading = new_heading;
+        let mut steps = 0;
+        while let Some(next) = ahead(&location, &heading) {
+            location = next;
+            steps += 1;
+        }
+        debug_println!("{turn:?} {steps} to {location:?}");
+        path.push(Segment { turn, steps });
+    }
+    path
+}
+
+// Most characters the robot accepts for the main routine or any one movement function
+const MAX_ROUTINE_LENGTH: usize = 20;
+// With commas in between, 10 calls is already 19 characters
+const MAX_CALLS: usize = MAX_ROUTINE_LENGTH / 2;
+const FUNCTION_NAMES: [char; 3] = ['A', 'B', 'C'];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Routines {
+    // Indexes into functions
+    main: Vec<usize>,
+    functions: Vec<Vec<Segment>>,
+}
+
+impl Routines {
+    fn main_routine(&self) -> String {
+        self.main
+            .iter()
+            .map(|index| FUNCTION_NAMES[*index].to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // Always three, padding with a harmless function if the path didn't need them all
+    fn functions(&self) -> Vec<String> {
+        (0..FUNCTION_NAMES.len())
+            .map(|index| match self.functions.get(index) {
+                Some(function) => encode(function),
+                None => "L,0".to_string(),
+            })
+            .collect()
+    }
+}
+
+/*
+ Split the path into at most three repeated functions.  Whatever's next on the path has to be
+ either a function we've already got, or the start of a new one, so try each of those in turn
+ and backtrack when we run out of functions or calls.
+*/
+fn compress(path: &[Segment]) -> Option<Routines> {
+    fn search(path: &[Segment], routines: &mut Routines) -> bool {
+        if path.is_empty() {
+            return true;
+        }
+        if routines.main.len() == MAX_CALLS {
+            return false;
+        }
+        for index in 0..routines.functions.len() {
+            if path.starts_with(&routines.functions[index]) {
+                routines.main.push(index);
+                let length = routines.functions[index].len();
+                if search(&path[length..], routines) {
+                    return true;
+                }
+                routines.main.pop();
+            }
+        }
+        if routines.functions.len() < FUNCTION_NAMES.len() {
+            for length in 1..=path.len() {
+                if encode(&path[..length]).len() > MAX_ROUTINE_LENGTH {
+                    break;
+                }
+                routines.main.push(routines.functions.len());
+                routines.functions.push(path[..length].to_vec());
+                if search(&path[length..], routines) {
+                    return true;
+                }
+                routines.functions.pop();
+                routines.main.pop();
+            }
+        }
+        false
+    }
+
+    let mut routines = Routines {
+        main: vec![],
+        functions: vec![],
+    };
+    if search(path, &mut routines) {
+        Some(routines)
+    } else {
+        None
+    }
+}
+
+fn camera_view(program: &[isize]) -> Scaffold {
+    let mut vm = VM::new(program.to_owned());
+    vm.run();
+    let view = take_output(&mut vm).text;
+    debug_println!("{view}");
+    parse_view(&view)
+}
+
+fn part_one(program: &[isize]) -> isize {
+    /*
+    What is the sum of the alignment parameters for the scaffold intersections?
+    */
+    let scaffold = camera_view(program);
+    let alignment: isize = intersections(&scaffold)
+        .iter()
+        .map(|point| point.x * point.y)
+        .sum();
+    alignment
+}
+
+fn part_two(program: &[isize]) -> isize {
+    /*
+    After visiting every part of the scaffold at least once, how much dust does the vacuum robot
+    report it has collected?
+    */
+    let path = find_path(&camera_view(program));
+    info!("Path: {}", encode(&path));
+    let Some(routines) = compress(&path) else {
+        panic!("Couldn't fit the path into three movement functions");
+    };
+
+    let mut vm = VM::new(program.to_owned());
+    // Wake the robot up
+    vm.set_memory(0, 2);
+    queue_line(&mut vm, &routines.main_routine());
+    for function in routines.functions() {
+        queue_line(&mut vm, &function);
+    }
+    // No continuous video feed, thanks
+    queue_line(&mut vm, "n");
+    vm.run();
+
+    let output = take_output(&mut vm);
+    match output.value() {
+        Some(dust) => dust,
+        None => panic!("Robot didn't report any dust:\n{}", output.text),
+    }
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Vec<isize>;
+    type One = isize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        17
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> isize {
+        part_one(program)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    // What the robot actually does when running the routines
+    fn expand(routines: &Routines) -> Vec<Segment> {
+        routines
+            .main
+            .iter()
+            .flat_map(|index| routines.functions[*index].iter().copied())
+            .collect()
+    }
+
+    const PART_ONE_EXAMPLE: &str = "\
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..
+";
+
+    const PART_TWO_EXAMPLE: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+";
+
+    // Round a loop and back over its own start, so the path crosses itself
+    const CROSSED_LOOP: &str = "\
+....#####
+....#...#
+....#...#
+^########
+....#....
+....#....
+";
+
+    #[test]
+    fn test_intersections() {
+        let scaffold = parse_view(PART_ONE_EXAMPLE);
+        assert_eq!(scaffold.robot, Point { x: 10, y: 6 });
+        assert_eq!(scaffold.heading, Direction::North);
+        let found = intersections(&scaffold);
+        assert_eq!(
+            found,
+            vec![
+                Point { x: 2, y: 2 },
+                Point { x: 2, y: 4 },
+                Point { x: 6, y: 4 },
+                Point { x: 10, y: 4 },
+            ]
+        );
+        let alignment: isize = found.iter().map(|point| point.x * point.y).sum();
+        assert_eq!(alignment, 76);
+    }
+
+    #[rstest]
+    #[case(
+        PART_TWO_EXAMPLE,
+        "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+    )]
+    #[case(CROSSED_LOOP, "R,8,L,3,L,4,L,5")]
+    fn test_find_path(#[case] view: &str, #[case] expected: &str) {
+        let path = find_path(&parse_view(view));
+        assert_eq!(encode(&path), expected);
+    }
+
+    #[rstest]
+    #[case(PART_TWO_EXAMPLE)]
+    #[case(CROSSED_LOOP)]
+    fn test_compress(#[case] view: &str) {
+        let path = find_path(&parse_view(view));
+        // An empty path would compress to nothing without trying
+        assert!(!path.is_empty());
+        let routines = compress(&path).unwrap();
+        assert_eq!(expand(&routines), path);
+        assert!(routines.main_routine().len() <= MAX_ROUTINE_LENGTH);
+        for function in routines.functions() {
+            assert!(function.len() <= MAX_ROUTINE_LENGTH);
+        }
+    }
+
+    #[test]
+    fn test_compress_all_functions() {
+        // Needs all three functions, called in different orders
+        let a = Segment {
+            turn: Turn::Left,
+            steps: 10,
+        };
+        let b = Segment {
+            turn: Turn::Right,
+            steps: 12,
+        };
+        let c = Segment {
+            turn: Turn::Left,
+            steps: 4,
+        };
+        let path = vec![a, b, a, b, c, a, b, c, c, a, b];
+        let routines = compress(&path).unwrap();
+        assert_eq!(expand(&routines), path);
+    }
+
+    #[test]
+    fn test_compress_impossible() {
+        // Five different segments, each too long to pair up with another inside 20 characters
+        let path: Vec<Segment> = (1..=5)
+            .map(|steps| Segment {
+                turn: Turn::Left,
+                steps: steps * 10_000_000,
+            })
+            .collect();
+        assert_eq!(compress(&path), None);
+    }
+}
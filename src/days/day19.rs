@@ -0,0 +1,208 @@
+use image::Rgb;
+use log::info;
+
+use crate::probe::Probe;
+use crate::render::{Palette, Renderer};
+use crate::solution::Solution;
+use crate::vm::parse_program;
+use crate::{debug_println, DenseGrid, Grid, Point};
+
+/*
+ The program answers a single question per run: given an x and a y, is that point being pulled
+ by the tractor beam (1) or not (0)?  Then it halts, so every point needs a fresh copy, which is
+ what Probe is for.
+
+ The beam is a cone coming out of the emitter at 0,0, with y increasing away from it.  Close to
+ the emitter it's narrow enough that some rows miss it entirely.
+*/
+
+fn in_beam(probe: &mut Probe, x: isize, y: isize) -> bool {
+    match probe.run(&[x, y]).front() {
+        Some(0) => false,
+        Some(1) => true,
+        other => panic!("Unexpected answer for {x},{y}: {other:?}"),
+    }
+}
+
+/*
+ Walk down the left edge of the beam one row at a time, treating each row as the bottom of the
+ square.  The left edge never moves left, so each row only needs a few probes to find it.  The
+ square fits once the point `size - 1` to the right and `size - 1` up from that edge is also in
+ the beam, and the first row where that happens is the one closest to the emitter.
+*/
+fn find_square<F: FnMut(isize, isize) -> bool>(mut in_beam: F, size: isize) -> Point<isize> {
+    let mut left = 0;
+    let mut bottom = size - 1;
+    loop {
+        // Rows near the emitter can be empty, so don't search forever for an edge
+        let limit = left + 10 * (bottom + 1);
+        let edge = (left..=limit).find(|x| in_beam(*x, bottom));
+        if let Some(x) = edge {
+            left = x;
+            let top = bottom - (size - 1);
+            if in_beam(x + size - 1, top) {
+                return Point { x, y: top };
+            }
+        }
+        bottom += 1;
+    }
+}
+
+// White where the beam pulls, with a legend for the picture
+struct Colours;
+
+impl Palette<bool> for Colours {
+    fn colour(&self, pulled: &bool) -> Rgb<u8> {
+        if *pulled {
+            Rgb([255, 255, 255])
+        } else {
+            Rgb([0, 0, 0])
+        }
+    }
+
+    fn legend(&self) -> Vec<(String, Rgb<u8>)> {
+        vec![("Pulled".to_owned(), self.colour(&true))]
+    }
+}
+
+fn part_one(program: &[isize], pictures: bool) -> usize {
+    /*
+    How many points are affected by the tractor beam in the 50x50 area closest to the emitter?
+    */
+    let mut probe = Probe::new(program.to_owned());
+    // The emitter's at the top left, with y growing away from it
+    let mut beam = DenseGrid::new(50, 50, false);
+    for y in 0..50 {
+        for x in 0..50 {
+            beam.set(Point { x, y }, in_beam(&mut probe, x, y));
+        }
+    }
+    if pictures {
+        info!("Saving image day_19.png");
+        Renderer::new()
+            .scale(8)
+            .legend(true)
+            .save(&beam, &Colours, "day_19.png")
+            .unwrap();
+    }
+    beam.iter().filter(|(_, pulled)| **pulled).count()
+}
+
+fn part_two(program: &[isize]) -> isize {
+    /*
+    Find the 100x100 square closest to the emitter that fits entirely within the tractor beam;
+    within that square, find the point closest to the emitter. What value do you get if you take
+    that point's X coordinate, multiply it by 10000, then add the point's Y coordinate?
+    */
+    let mut probe = Probe::new(program.to_owned());
+    let corner = find_square(|x, y| in_beam(&mut probe, x, y), 100);
+    debug_println!("Square's top left is {corner:?}");
+    info!("Probed {} points", probe.runs());
+    corner.x * 10000 + corner.y
+}
+
+#[derive(Default)]
+pub struct Day19 {
+    /// Save a picture of the beam near the emitter
+    pub pictures: bool,
+}
+
+impl Solution for Day19 {
+    type Input = Vec<isize>;
+    type One = usize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        19
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> usize {
+        part_one(program, self.pictures)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    // Part two's example, with the 10x10 square marked as O
+    const EXAMPLE: &str = "\
+#.......................................
+.#......................................
+..##....................................
+...###..................................
+....###.................................
+.....####...............................
+......#####.............................
+......######............................
+.......#######..........................
+........########........................
+.........#########......................
+..........#########.....................
+...........##########...................
+...........############.................
+............############................
+.............#############..............
+..............##############............
+...............###############..........
+................###############.........
+................#################.......
+.................########OOOOOOOOOO.....
+..................#######OOOOOOOOOO#....
+...................######OOOOOOOOOO###..
+....................#####OOOOOOOOOO#####
+.....................####OOOOOOOOOO#####
+.....................####OOOOOOOOOO#####
+......................###OOOOOOOOOO#####
+.......................##OOOOOOOOOO#####
+........................#OOOOOOOOOO#####
+.........................OOOOOOOOOO#####
+..........................##############
+..........................##############
+...........................#############
+............................############
+.............................###########";
+
+    fn example_beam(x: isize, y: isize) -> bool {
+        let row = EXAMPLE.lines().nth(y as usize);
+        row.and_then(|row| row.chars().nth(x as usize))
+            .is_some_and(|c| c != '.')
+    }
+
+    // A beam between two slopes, so it carries on forever
+    fn cone(x: isize, y: isize) -> bool {
+        3 * y >= 4 * x && 2 * y <= 3 * x
+    }
+
+    // Try every top left corner, nearest to the emitter first
+    fn brute_force<F: Fn(isize, isize) -> bool>(in_beam: F, size: isize) -> Point<isize> {
+        (0..1000)
+            .flat_map(|distance| (0..=distance).map(move |x| (x, distance - x)))
+            .find(|(x, y)| (0..size).all(|dy| (0..size).all(|dx| in_beam(x + dx, y + dy))))
+            .map(|(x, y)| Point { x, y })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(find_square(example_beam, 10), Point { x: 25, y: 20 });
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(5)]
+    #[case(10)]
+    fn test_matches_brute_force(#[case] size: isize) {
+        assert_eq!(find_square(cone, size), brute_force(cone, size));
+    }
+}
@@ -0,0 +1,93 @@
+use log::info;
+
+use crate::debug_println;
+use crate::search::{patch_space, Search};
+use crate::solution::Solution;
+use crate::vm::{parse_program, VM};
+
+fn part_two(input: &[isize]) -> isize {
+    /*
+    To complete the gravity assist, you need to determine what
+    pair of inputs produces the output 19690720.
+
+    The inputs should still be provided to the program by
+    replacing the values at addresses 1 and 2, just like before.
+
+    In this program, the value placed in address 1 is called the
+    noun, and the value placed in address 2 is called the verb.
+    Each of the two input values will be between 0 and 99, inclusive.
+
+    Find the input noun and verb that cause the program to produce
+    the output 19690720. What is 100 * noun + verb?
+     */
+    let report = Search::new(VM::new(input.to_owned())).run(patch_space(&[1, 2], 0..=99), |vm| {
+        vm.memory()[0] == 19_690_720
+    });
+    info!("{report}");
+    let Some(found) = report.found else {
+        panic!("No noun and verb give 19690720");
+    };
+    let [(_, noun), (_, verb)] = found.candidate.patches[..] else {
+        unreachable!("Patches are always a noun and a verb");
+    };
+    100 * noun + verb
+}
+
+fn part_one(input: &[isize]) -> isize {
+    /*
+    Once you have a working computer, the first step is to
+    restore the gravity assist program (your puzzle input)
+    to the "1202 program alarm" state it had just before
+    the last computer caught fire. To do this, before running
+    the program, replace position 1 with the value 12 and
+    replace position 2 with the value 2. What value is left
+    at position 0 after the program halts?
+     */
+    let mut vm = VM::new(input.to_owned());
+    debug_println!("{:?}", vm);
+    // replace position 1 with the value 12
+    vm.set_memory(1, 12);
+    debug_println!("{:?}", vm);
+    // replace position 2 with the value 2
+    vm.set_memory(2, 2);
+    debug_println!("{:?}", vm);
+    vm.run();
+    vm.get_memory(0)
+}
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<isize>;
+    type One = isize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        2
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> isize {
+        part_one(program)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::parse_input;
+
+    #[test]
+    fn test_real_input() {
+        let program = parse_input(&Day2);
+        assert_eq!(Day2.part_one(&program), 6_327_510);
+        assert_eq!(Day2.part_two(&program), 4112);
+    }
+}
@@ -0,0 +1,461 @@
+use std::fmt;
+
+use log::info;
+
+use crate::ascii::{queue_line, take_output};
+use crate::debug_println;
+use crate::solution::Solution;
+use crate::vm::{parse_program, VM};
+
+/*
+ The springdroid walks along the hull one tile at a time, and has to jump over the holes.  We
+ program it in springscript, up to 15 instructions of:
+ * AND X Y: Y = X and Y
+ * OR X Y: Y = X or Y
+ * NOT X Y: Y = not X
+ X can be any sensor or register, Y has to be a register.  Sensor A is true if there's ground one
+ tile ahead, B two tiles ahead, and so on.  T is scratch, and if J ends up true, the droid jumps,
+ landing four tiles ahead.  The script ends with WALK (sensors A to D) or RUN (A to I).
+
+ If the droid makes it, the program outputs the hull damage as a single big number.  If not, it
+ draws an animation of the droid falling in a hole.
+
+ Rather than working out scripts by hand, we search for them.  Every failure tells us about a
+ stretch of hull, and we can simulate the droid on those locally, so only scripts that survive
+ every hull seen so far are worth another run of the VM.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Register {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    T,
+    J,
+}
+
+const SENSORS: [Register; 9] = [
+    Register::A,
+    Register::B,
+    Register::C,
+    Register::D,
+    Register::E,
+    Register::F,
+    Register::G,
+    Register::H,
+    Register::I,
+];
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Instruction {
+    op: Op,
+    source: Register,
+    target: Register,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Not => "NOT",
+        };
+        write!(f, "{op} {} {}", self.source, self.target)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    fn sensors(&self) -> &'static [Register] {
+        match self {
+            Mode::Walk => &SENSORS[..4],
+            Mode::Run => &SENSORS,
+        }
+    }
+}
+
+const MAX_INSTRUCTIONS: usize = 15;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Script {
+    instructions: Vec<Instruction>,
+}
+
+impl Script {
+    fn push(&mut self, op: Op, source: Register, target: Register) {
+        self.instructions.push(Instruction { op, source, target });
+    }
+
+    // The lines to feed the droid, ending with the command to start it
+    fn compile(&self, mode: Mode) -> Vec<String> {
+        let mut lines: Vec<String> = self.instructions.iter().map(ToString::to_string).collect();
+        lines.push(match mode {
+            Mode::Walk => "WALK".to_string(),
+            Mode::Run => "RUN".to_string(),
+        });
+        lines
+    }
+
+    // Whether the droid would jump, given what's in front of it.  `ahead[0]` is sensor A.
+    fn jumps(&self, ahead: &[bool]) -> bool {
+        let mut t = false;
+        let mut j = false;
+        for instruction in &self.instructions {
+            let source = match instruction.source {
+                Register::T => t,
+                Register::J => j,
+                sensor => ahead[sensor as usize],
+            };
+            let target = match instruction.target {
+                Register::T => &mut t,
+                Register::J => &mut j,
+                sensor => panic!("Can't write to sensor {sensor}"),
+            };
+            *target = match instruction.op {
+                Op::And => source && *target,
+                Op::Or => source || *target,
+                Op::Not => !source,
+            };
+        }
+        j
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            writeln!(f, "{instruction}")?;
+        }
+        Ok(())
+    }
+}
+
+// true is ground, false is a hole.  The droid starts on the first tile.
+type Hull = Vec<bool>;
+
+// Tiles past the end of what we've been shown are assumed to be ground
+fn ground(hull: &Hull, position: usize) -> bool {
+    hull.get(position).copied().unwrap_or(true)
+}
+
+// Simulate the droid locally.  It's made it once it's past the last hole.
+fn survives(script: &Script, hull: &Hull) -> bool {
+    let mut position = 0;
+    while position < hull.len() {
+        let ahead: Vec<bool> = (1..=SENSORS.len())
+            .map(|distance| ground(hull, position + distance))
+            .collect();
+        position += if script.jumps(&ahead) { 4 } else { 1 };
+        if !ground(hull, position) {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    Damage(isize),
+    Fell { frame: String, hull: Hull },
+}
+
+// Every frame of the animation shows the same bit of hull, on the first line starting with
+// ground (the droid's standing on it)
+fn parse_failure(text: &str) -> Outcome {
+    let Some(hull) = text.lines().find(|line| line.starts_with('#')) else {
+        panic!("Droid didn't make it, and didn't say why:\n{text}");
+    };
+    let frame = text
+        .trim_end()
+        .rsplit("\n\n")
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    Outcome::Fell {
+        frame,
+        hull: hull.chars().map(|c| c == '#').collect(),
+    }
+}
+
+fn run_script(program: &[isize], script: &Script, mode: Mode) -> Outcome {
+    let mut vm = VM::new(program.to_owned());
+    for line in script.compile(mode) {
+        queue_line(&mut vm, &line);
+    }
+    vm.run();
+    let output = take_output(&mut vm);
+    match output.value() {
+        Some(damage) => Outcome::Damage(damage),
+        None => parse_failure(&output.text),
+    }
+}
+
+// Subsets of `registers`, smallest first
+fn subsets(registers: &[Register]) -> Vec<Vec<Register>> {
+    let mut subsets: Vec<Vec<Register>> = (0..1 << registers.len())
+        .map(|mask: usize| {
+            registers
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .map(|(_, register)| *register)
+                .collect()
+        })
+        .collect();
+    subsets.sort_by_key(Vec::len);
+    subsets
+}
+
+/*
+ Every script we try has the same shape:
+
+    J = (!near1 or !near2 ...) and (far1 and far2 ...) and (other1 or other2 ...)
+
+ i.e. jump if there's a hole coming up soon, it's safe to land, and there's some way onwards
+ after landing.  "Near" is A to C, the others come from D onwards, and the groups never overlap.
+ Shortest scripts come first.
+*/
+fn candidates(mode: Mode) -> Vec<Script> {
+    let (near, far) = mode.sensors().split_at(3);
+    let mut scripts = vec![];
+    for holes in subsets(near).into_iter().skip(1) {
+        for landing in subsets(far) {
+            let rest: Vec<Register> = far
+                .iter()
+                .filter(|register| !landing.contains(register))
+                .copied()
+                .collect();
+            for onwards in subsets(&rest) {
+                let mut script = Script::default();
+                script.push(Op::Not, holes[0], Register::J);
+                for register in &holes[1..] {
+                    script.push(Op::Not, *register, Register::T);
+                    script.push(Op::Or, Register::T, Register::J);
+                }
+                for register in &landing {
+                    script.push(Op::And, *register, Register::J);
+                }
+                if let Some((first, others)) = onwards.split_first() {
+                    script.push(Op::Not, *first, Register::T);
+                    script.push(Op::Not, Register::T, Register::T);
+                    for register in others {
+                        script.push(Op::Or, *register, Register::T);
+                    }
+                    script.push(Op::And, Register::T, Register::J);
+                }
+                if script.instructions.len() <= MAX_INSTRUCTIONS {
+                    scripts.push(script);
+                }
+            }
+        }
+    }
+    scripts.sort_by_key(|script| script.instructions.len());
+    scripts
+}
+
+// `attempt` runs the script for real.  Returns the first script to survive, and the damage.
+fn search<F: FnMut(&Script) -> Outcome>(mode: Mode, mut attempt: F) -> Option<(Script, isize)> {
+    let mut hulls: Vec<Hull> = vec![];
+    for script in candidates(mode) {
+        if !hulls.iter().all(|hull| survives(&script, hull)) {
+            continue;
+        }
+        match attempt(&script) {
+            Outcome::Damage(damage) => return Some((script, damage)),
+            Outcome::Fell { frame, hull } => {
+                debug_println!("{script}fell:\n{frame}");
+                hulls.push(hull);
+            }
+        }
+    }
+    None
+}
+
+fn solve(program: &[isize], mode: Mode) -> isize {
+    let mut attempts = 0;
+    let found = search(mode, |script| {
+        attempts += 1;
+        run_script(program, script, mode)
+    });
+    match found {
+        Some((script, damage)) => {
+            info!("Found a script after {attempts} attempts:\n{script}");
+            damage
+        }
+        None => panic!("No script gets the droid across"),
+    }
+}
+
+fn part_one(program: &[isize]) -> isize {
+    /*
+    Program the springdroid with logic that allows it to survey the hull without falling into
+    space. What amount of hull damage does it report?
+    */
+    solve(program, Mode::Walk)
+}
+
+fn part_two(program: &[isize]) -> isize {
+    /*
+    Successfully survey the rest of the hull by ending your program with RUN. What amount of hull
+    damage does the springdroid now report?
+    */
+    solve(program, Mode::Run)
+}
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    type Input = Vec<isize>;
+    type One = isize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        21
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> isize {
+        part_one(program)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+
+    fn hull(text: &str) -> Hull {
+        text.chars().map(|c| c == '#').collect()
+    }
+
+    // Jump if there's a hole in the next three tiles and somewhere to land
+    fn walking_script() -> Script {
+        let mut script = Script::default();
+        script.push(Op::Not, Register::A, Register::J);
+        script.push(Op::Not, Register::B, Register::T);
+        script.push(Op::Or, Register::T, Register::J);
+        script.push(Op::Not, Register::C, Register::T);
+        script.push(Op::Or, Register::T, Register::J);
+        script.push(Op::And, Register::D, Register::J);
+        script
+    }
+
+    #[test]
+    fn test_compile() {
+        let mut script = Script::default();
+        script.push(Op::Not, Register::A, Register::J);
+        script.push(Op::And, Register::T, Register::J);
+        assert_eq!(
+            script.compile(Mode::Walk),
+            vec!["NOT A J", "AND T J", "WALK"]
+        );
+        assert_eq!(script.compile(Mode::Run), vec!["NOT A J", "AND T J", "RUN"]);
+    }
+
+    #[rstest]
+    #[case("#####.###########", true)]
+    #[case("#####..#.########", true)]
+    #[case("#####...#########", true)]
+    // Jumps as early as it can, which lands it with no way on
+    #[case("########.#.##.###", false)]
+    fn test_survives(#[case] text: &str, #[case] expected: bool) {
+        assert_eq!(survives(&walking_script(), &hull(text)), expected);
+    }
+
+    #[test]
+    fn test_parse_failure() {
+        let text = "\
+Input instructions:
+
+Walking...
+
+Didn't make it across:
+
+.................
+.................
+@................
+#####.###########
+
+.................
+.................
+.....@...........
+#####.###########
+
+";
+        assert_eq!(
+            parse_failure(text),
+            Outcome::Fell {
+                frame: ".................\n.................\n.....@...........\n#####.###########"
+                    .to_string(),
+                hull: hull("#####.###########"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        let walk = candidates(Mode::Walk);
+        assert_eq!(walk[0].to_string(), "NOT A J\n");
+        assert!(walk.contains(&walking_script()));
+        for script in candidates(Mode::Run) {
+            assert!(script.instructions.len() <= MAX_INSTRUCTIONS);
+        }
+    }
+
+    // Stands in for the VM, failing on the first of its hulls the script doesn't survive
+    fn attempt_on<'a>(hulls: &'a [&'a str]) -> impl FnMut(&Script) -> Outcome + 'a {
+        move |script| {
+            for text in hulls {
+                if !survives(script, &hull(text)) {
+                    return Outcome::Fell {
+                        frame: String::new(),
+                        hull: hull(text),
+                    };
+                }
+            }
+            Outcome::Damage(19_355_000)
+        }
+    }
+
+    #[rstest]
+    #[case(Mode::Walk, vec!["#####.###########", "#####..#.########", "#####...#########", "#####.#..########"])]
+    #[case(Mode::Run, vec!["#####.###########", "#####...#########", "########.#.##.###", "#####.##.##.#.###", "#####..##.##.####"])]
+    fn test_search(#[case] mode: Mode, #[case] hulls: Vec<&str>) {
+        let (script, damage) = search(mode, attempt_on(&hulls)).unwrap();
+        assert_eq!(damage, 19_355_000);
+        for text in hulls {
+            assert!(survives(&script, &hull(text)), "{script}fell on {text}");
+        }
+    }
+}
@@ -0,0 +1,646 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+
+use log::info;
+
+use crate::ascii::{queue_line, take_output};
+use crate::debug_println;
+use crate::solution::Solution;
+use crate::vm::{parse_program, Snapshot, VM};
+
+/*
+ A text adventure.  The droid wanders around Santa's ship, picking things up, and needs to get
+ past a pressure sensitive floor that only lets it through carrying exactly the right weight.
+ Getting through gets us the password for the airlock.
+
+ Every room comes out looking like:
+
+    == Hull Breach ==
+    You got in through a hole in the floor here.
+
+    Doors here lead:
+    - north
+    - south
+
+    Items here:
+    - mutex
+
+    Command?
+
+ and commands are "north", "take mutex", "drop mutex", "inv" and so on.  Some items are traps:
+ picking them up ends the game, or gets the program stuck in a loop, or stops the droid moving.
+
+ Run with --interactive to play by hand, otherwise it plays itself.
+*/
+
+// Enough for any one command, but not for the infinite loop
+const STEP_BUDGET: usize = 10_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reply {
+    Text(String),
+    // The program finished, with its parting words
+    Over(String),
+    // Used up STEP_BUDGET without asking for another command
+    Hung,
+}
+
+// Anything that plays the game.  Cloning has to give an independent copy, so we can try things
+// out without committing to them.
+trait Adventure: Clone {
+    // An empty command just collects whatever the game's said so far
+    fn send(&mut self, command: &str) -> Reply;
+}
+
+impl Adventure for VM {
+    fn send(&mut self, command: &str) -> Reply {
+        if !command.is_empty() {
+            queue_line(self, command);
+        }
+        let stopped = match self.try_run_for(STEP_BUDGET) {
+            Ok(stopped) => stopped,
+            Err(error) => panic!("{error}"),
+        };
+        let text = take_output(self).text;
+        if !stopped {
+            Reply::Hung
+        } else if self.finished() {
+            Reply::Over(text)
+        } else {
+            Reply::Text(text)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Room {
+    name: String,
+    description: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+// The last room described in `text`.  There can be more than one, e.g. when the floor throws us
+// back out to the checkpoint.
+fn parse_room(text: &str) -> Option<Room> {
+    let start = text.rfind("== ")?;
+    let mut lines = text[start..].lines();
+    let name = lines.next()?.trim_matches(|c| c == '=' || c == ' ');
+    let mut room = Room {
+        name: name.to_string(),
+        description: lines.next().unwrap_or_default().to_string(),
+        ..Room::default()
+    };
+    let mut list = None;
+    for line in lines {
+        match line {
+            "Doors here lead:" => list = Some(&mut room.doors),
+            "Items here:" => list = Some(&mut room.items),
+            _ => match (line.strip_prefix("- "), list.as_mut()) {
+                (Some(entry), Some(list)) => list.push(entry.to_string()),
+                _ => list = None,
+            },
+        }
+    }
+    Some(room)
+}
+
+fn opposite(direction: &str) -> &'static str {
+    match direction {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Unknown direction {direction}"),
+    }
+}
+
+// The airlock password, from the text we get on finally getting through
+fn parse_password(text: &str) -> Option<String> {
+    let start = text.find("typing ")? + "typing ".len();
+    let password: String = text[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    (!password.is_empty()).then_some(password)
+}
+
+// Which item to toggle to get from each combination to the next, changing one item at a time so
+// that every combination of `count` items gets tried
+fn gray_code_toggles(count: usize) -> Vec<usize> {
+    (1..1_usize << count)
+        .map(|i| i.trailing_zeros() as usize)
+        .collect()
+}
+
+struct Explorer<A: Adventure> {
+    game: A,
+    rooms: HashMap<String, Room>,
+    // Room name -> direction -> room name
+    links: HashMap<String, HashMap<String, String>>,
+    inventory: Vec<String>,
+    // The room before the pressure sensitive floor, and which way the floor is
+    checkpoint: Option<(String, String)>,
+}
+
+impl<A: Adventure> Explorer<A> {
+    fn new(game: A) -> Self {
+        Explorer {
+            game,
+            rooms: HashMap::new(),
+            links: HashMap::new(),
+            inventory: vec![],
+            checkpoint: None,
+        }
+    }
+
+    fn send(&mut self, command: &str) -> String {
+        debug_println!("> {command}");
+        match self.game.send(command) {
+            Reply::Text(text) => text,
+            other => panic!("Game ended unexpectedly after {command:?}: {other:?}"),
+        }
+    }
+
+    // Try picking the item up on a copy of the game, and check we can still move afterwards
+    fn is_safe(&self, room: &Room, item: &str) -> bool {
+        let mut trial = self.game.clone();
+        if !matches!(trial.send(&format!("take {item}")), Reply::Text(_)) {
+            return false;
+        }
+        match room.doors.first().map(|door| trial.send(door)) {
+            Some(Reply::Text(text)) => !text.contains("can't move"),
+            Some(_) => false,
+            None => true,
+        }
+    }
+
+    fn link(&mut self, from: &str, direction: &str, to: &str) {
+        self.links
+            .entry(from.to_string())
+            .or_default()
+            .insert(direction.to_string(), to.to_string());
+    }
+
+    // Depth first, walking the droid back the way it came after each branch
+    fn explore(&mut self, room: Room) {
+        for item in &room.items {
+            if self.is_safe(&room, item) {
+                self.send(&format!("take {item}"));
+                self.inventory.push(item.clone());
+            } else {
+                info!("Leaving the {item} in {}", room.name);
+            }
+        }
+        self.rooms.insert(room.name.clone(), room.clone());
+
+        for door in &room.doors {
+            let known = self
+                .links
+                .get(&room.name)
+                .is_some_and(|links| links.contains_key(door));
+            if known {
+                continue;
+            }
+            let Some(next) = parse_room(&self.send(door)) else {
+                panic!("Went {door} from {} and got lost", room.name);
+            };
+            if next.name == room.name {
+                // Thrown back by the floor
+                self.checkpoint = Some((room.name.clone(), door.clone()));
+                continue;
+            }
+            self.link(&room.name, door, &next.name);
+            self.link(&next.name, opposite(door), &room.name);
+            if !self.rooms.contains_key(&next.name) {
+                self.explore(next);
+            }
+            self.send(opposite(door));
+        }
+    }
+
+    // Directions from one room to another, through rooms we've seen
+    fn route(&self, from: &str, to: &str) -> Vec<String> {
+        let mut came_from: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                break;
+            }
+            for (direction, next) in self.links.get(room).into_iter().flatten() {
+                if next != from && !came_from.contains_key(next.as_str()) {
+                    came_from.insert(next, (room, direction));
+                    queue.push_back(next);
+                }
+            }
+        }
+        let mut route = vec![];
+        let mut room = to;
+        while room != from {
+            let Some((previous, direction)) = came_from.get(room) else {
+                panic!("No way from {from} to {to}");
+            };
+            route.push(direction.to_string());
+            room = previous;
+        }
+        route.reverse();
+        route
+    }
+
+    // Try every combination of items on the floor until one gets through
+    fn crack_floor(&mut self, start: &str) -> String {
+        let Some((checkpoint, floor)) = self.checkpoint.clone() else {
+            panic!("Never found the pressure sensitive floor");
+        };
+        for direction in self.route(start, &checkpoint) {
+            self.send(&direction);
+        }
+
+        let items = self.inventory.clone();
+        for item in &items {
+            self.send(&format!("drop {item}"));
+        }
+        let mut held = vec![false; items.len()];
+        let attempts =
+            std::iter::once(None).chain(gray_code_toggles(items.len()).into_iter().map(Some));
+        for toggle in attempts {
+            if let Some(index) = toggle {
+                let verb = if held[index] { "drop" } else { "take" };
+                self.send(&format!("{verb} {}", items[index]));
+                held[index] = !held[index];
+            }
+            match self.game.send(&floor) {
+                Reply::Over(text) => {
+                    let carrying: Vec<&String> = items
+                        .iter()
+                        .zip(&held)
+                        .filter(|(_, held)| **held)
+                        .map(|(item, _)| item)
+                        .collect();
+                    info!("Got through carrying {carrying:?}");
+                    return match parse_password(&text) {
+                        Some(password) => password,
+                        None => panic!("Got through, but no password:\n{text}"),
+                    };
+                }
+                Reply::Text(_) => {}
+                Reply::Hung => panic!("Game hung on the floor"),
+            }
+        }
+        panic!("No combination of {items:?} gets through");
+    }
+}
+
+fn solve<A: Adventure>(game: A) -> String {
+    let mut explorer = Explorer::new(game);
+    let Some(start) = parse_room(&explorer.send("")) else {
+        panic!("Game didn't start in a room");
+    };
+    let start_name = start.name.clone();
+    explorer.explore(start);
+    info!(
+        "Explored {} rooms, picked up {:?}",
+        explorer.rooms.len(),
+        explorer.inventory
+    );
+    explorer.crack_floor(&start_name)
+}
+
+fn part_one(program: &[isize]) -> String {
+    /*
+    Look around the ship and see if you can find the password for the main airlock.
+    */
+    solve(VM::new(program.to_owned()))
+}
+
+/// Play the game yourself.  As well as the game's own commands:
+/// * `n`, `s`, `e`, `w`: short for the directions
+/// * `history`: list the commands so far, and `!N` to repeat the Nth
+/// * `save [name]`, `load [name]`: keep and restore the whole VM state
+/// * `quit`, or the end of input
+///
+/// # Panics
+///
+/// If it can't read stdin
+pub fn interactive(program: &[isize]) {
+    let stdin = io::stdin();
+    let mut vm = VM::new(program.to_owned());
+    let mut history: Vec<String> = vec![];
+    let mut saves: HashMap<String, Snapshot> = HashMap::new();
+    let mut reply = vm.send("");
+    loop {
+        match &reply {
+            Reply::Text(text) => print!("{text}"),
+            Reply::Over(text) => {
+                print!("{text}");
+                println!("Game over.  Use load to go back to a save, or quit.");
+            }
+            Reply::Hung => println!("The game seems to be stuck.  Use load, or quit."),
+        }
+
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.read_line(&mut line).unwrap() == 0 {
+            println!();
+            break;
+        }
+        let mut command = line.trim().to_string();
+        if let Some(number) = command.strip_prefix('!') {
+            match number.parse::<usize>().ok().and_then(|n| history.get(n)) {
+                Some(previous) => command.clone_from(previous),
+                None => {
+                    println!("No command {number} in the history");
+                    continue;
+                }
+            }
+        }
+        let (verb, argument) = command.split_once(' ').unwrap_or((&command, "default"));
+        match verb {
+            "quit" => break,
+            "history" => {
+                for (number, previous) in history.iter().enumerate() {
+                    println!("{number:>4}  {previous}");
+                }
+                continue;
+            }
+            "save" => {
+                saves.insert(argument.to_string(), vm.snapshot());
+                println!("Saved as {argument}");
+                continue;
+            }
+            "load" => {
+                match saves.get(argument) {
+                    Some(snapshot) => {
+                        vm.restore(snapshot);
+                        reply = Reply::Text(String::new());
+                        println!("Loaded {argument}");
+                    }
+                    None => println!("Nothing saved as {argument}"),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if !matches!(reply, Reply::Text(_)) {
+            println!("The game's over.  Use load, or quit.");
+            continue;
+        }
+        history.push(command.clone());
+        let expanded = match command.as_str() {
+            "n" => "north",
+            "s" => "south",
+            "e" => "east",
+            "w" => "west",
+            other => other,
+        };
+        reply = vm.send(expanded);
+    }
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    type Input = Vec<isize>;
+    type One = String;
+    type Two = String;
+
+    fn day(&self) -> u32 {
+        25
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> String {
+        part_one(program)
+    }
+
+    fn part_two(&self, _program: &Vec<isize>) -> String {
+        // There's no puzzle for part two, just the last star
+        String::from("Merry Christmas!")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::*;
+    use std::rc::Rc;
+
+    const HULL_BREACH: &str = "\n\n\n== Hull Breach ==\nYou got in through a hole in the floor here. To keep your ship from also freezing, the hole has been sealed.\n\nDoors here lead:\n- north\n- east\n- south\n\nItems here:\n- mutex\n\nCommand?\n";
+
+    #[test]
+    fn test_parse_room() {
+        let room = parse_room(HULL_BREACH).unwrap();
+        assert_eq!(room.name, "Hull Breach");
+        assert!(room.description.starts_with("You got in through a hole"));
+        assert_eq!(room.doors, vec!["north", "east", "south"]);
+        assert_eq!(room.items, vec!["mutex"]);
+    }
+
+    #[test]
+    fn test_parse_room_after_ejection() {
+        let text = "\n\n\n== Pressure-Sensitive Floor ==\nAnalyzing...\n\nDoors here lead:\n- west\n\nA loud, robotic voice says \"Alert! Droids on this ship are heavier than the detected value!\" and you are ejected back to the checkpoint.\n\n\n\n== Security Checkpoint ==\nIn the next room, a pressure-sensitive floor will verify your identity.\n\nDoors here lead:\n- north\n- east\n\nCommand?\n";
+        let room = parse_room(text).unwrap();
+        assert_eq!(room.name, "Security Checkpoint");
+        assert_eq!(room.doors, vec!["north", "east"]);
+        assert!(room.items.is_empty());
+    }
+
+    #[rstest]
+    #[case(
+        "You should be able to get in by typing 2424308736 on the keypad at the main airlock.",
+        Some("2424308736")
+    )]
+    #[case("Command?", None)]
+    fn test_parse_password(#[case] text: &str, #[case] expected: Option<&str>) {
+        assert_eq!(parse_password(text), expected.map(ToString::to_string));
+    }
+
+    #[test]
+    fn test_gray_code_toggles() {
+        let mut held = 0_usize;
+        let mut seen = vec![held];
+        for index in gray_code_toggles(3) {
+            held ^= 1 << index;
+            seen.push(held);
+        }
+        seen.sort_unstable();
+        assert_eq!(seen, (0..8).collect::<Vec<usize>>());
+    }
+
+    struct MockRoom {
+        name: &'static str,
+        doors: Vec<(&'static str, usize)>,
+        items: Vec<&'static str>,
+    }
+
+    // A tiny ship for the explorer to work its way round.  Room 0 is the start, and room 4 is the
+    // floor, which wants exactly the mutex and the spool of cat6.
+    #[derive(Clone)]
+    struct MockShip {
+        rooms: Rc<Vec<MockRoom>>,
+        location: usize,
+        taken: Vec<&'static str>,
+        dropped: Vec<(usize, &'static str)>,
+        stuck: bool,
+    }
+
+    impl MockShip {
+        fn new() -> Self {
+            let rooms = vec![
+                MockRoom {
+                    name: "Hull Breach",
+                    doors: vec![("north", 1), ("east", 2)],
+                    items: vec!["mutex"],
+                },
+                MockRoom {
+                    name: "Kitchen",
+                    doors: vec![("south", 0)],
+                    items: vec!["molten lava", "spool of cat6"],
+                },
+                MockRoom {
+                    name: "Storage",
+                    doors: vec![("west", 0), ("east", 3)],
+                    items: vec!["giant electromagnet", "ornament"],
+                },
+                MockRoom {
+                    name: "Security Checkpoint",
+                    doors: vec![("west", 2), ("north", 4)],
+                    items: vec![],
+                },
+                MockRoom {
+                    name: "Pressure-Sensitive Floor",
+                    doors: vec![("south", 3)],
+                    items: vec![],
+                },
+            ];
+            MockShip {
+                rooms: Rc::new(rooms),
+                location: 0,
+                taken: vec![],
+                dropped: vec![],
+                stuck: false,
+            }
+        }
+
+        fn describe(&self, index: usize) -> String {
+            let room = &self.rooms[index];
+            let mut text = format!("\n\n\n== {} ==\nA room.\n\nDoors here lead:\n", room.name);
+            for (door, _) in &room.doors {
+                text += &format!("- {door}\n");
+            }
+            let items: Vec<&str> = room
+                .items
+                .iter()
+                .filter(|item| !self.taken.contains(item))
+                .copied()
+                .chain(
+                    self.dropped
+                        .iter()
+                        .filter(|(at, _)| *at == index)
+                        .map(|(_, item)| *item),
+                )
+                .collect();
+            if !items.is_empty() {
+                text += "\nItems here:\n";
+                for item in items {
+                    text += &format!("- {item}\n");
+                }
+            }
+            text + "\nCommand?\n"
+        }
+    }
+
+    impl Adventure for MockShip {
+        fn send(&mut self, command: &str) -> Reply {
+            if command.is_empty() {
+                return Reply::Text(self.describe(self.location));
+            }
+            if let Some(item) = command.strip_prefix("take ") {
+                let here = self.rooms[self.location].items.iter().copied();
+                let dropped = self.dropped.iter().filter(|(at, _)| *at == self.location);
+                let Some(item) = here
+                    .chain(dropped.map(|(_, item)| *item))
+                    .find(|candidate| *candidate == item)
+                else {
+                    return Reply::Text("You don't see that item here.\n".to_string());
+                };
+                self.dropped.retain(|(_, dropped)| *dropped != item);
+                self.taken.push(item);
+                return match item {
+                    "molten lava" => Reply::Over("You melt.\n".to_string()),
+                    "giant electromagnet" => {
+                        self.stuck = true;
+                        Reply::Text("The giant electromagnet is stuck to you.\n".to_string())
+                    }
+                    _ => Reply::Text(format!("You take the {item}.\n")),
+                };
+            }
+            if let Some(item) = command.strip_prefix("drop ") {
+                let Some(position) = self.taken.iter().position(|taken| *taken == item) else {
+                    return Reply::Text("You don't have that item.\n".to_string());
+                };
+                let item = self.taken.remove(position);
+                self.dropped.push((self.location, item));
+                return Reply::Text(format!("You drop the {item}.\n"));
+            }
+            if self.stuck {
+                return Reply::Text("You can't move!!\n".to_string());
+            }
+            let Some((_, next)) = self.rooms[self.location]
+                .doors
+                .iter()
+                .find(|(door, _)| *door == command)
+            else {
+                return Reply::Text("You can't go that way.\n".to_string());
+            };
+            if *next == 4 {
+                let mut carrying = self.taken.clone();
+                carrying.sort_unstable();
+                if carrying == ["mutex", "spool of cat6"] {
+                    return Reply::Over(
+                        "You may proceed. Get in by typing 1234 on the keypad.\n".to_string(),
+                    );
+                }
+                return Reply::Text(
+                    self.describe(4)
+                        + "\nYou are ejected back to the checkpoint.\n"
+                        + &self.describe(3),
+                );
+            }
+            self.location = *next;
+            Reply::Text(self.describe(*next))
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(MockShip::new()), "1234");
+    }
+
+    #[test]
+    fn test_explore() {
+        let mut explorer = Explorer::new(MockShip::new());
+        let start = parse_room(&explorer.send("")).unwrap();
+        explorer.explore(start);
+        assert_eq!(explorer.rooms.len(), 4);
+        explorer.inventory.sort_unstable();
+        assert_eq!(
+            explorer.inventory,
+            vec!["mutex", "ornament", "spool of cat6"]
+        );
+        assert_eq!(
+            explorer.checkpoint,
+            Some(("Security Checkpoint".to_string(), "north".to_string()))
+        );
+        assert_eq!(
+            explorer.route("Kitchen", "Security Checkpoint"),
+            vec!["south", "east", "east"]
+        );
+        // Back where it started
+        assert_eq!(explorer.game.location, 0);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::debug_println;
+use crate::solution::Solution;
+use crate::vm::{parse_program, VM};
+
+fn part_two(input: &[isize]) -> isize {
+    // For part 2, the input value should be 5
+    let mut vm = VM::new(input.to_owned());
+    vm.push_input(5);
+    debug_println!("{:?}", vm);
+    vm.run();
+    vm.pop_output()
+        .expect("The thermal radiator controller gave no diagnostic code")
+}
+
+fn part_one(input: &[isize]) -> isize {
+    // For part 1, the input value should be 1
+    let mut vm = VM::new(input.to_owned());
+    vm.push_input(1);
+    debug_println!("{:?}", vm);
+    vm.run();
+    vm.pop_output()
+        .expect("The air conditioner gave no diagnostic code")
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Vec<isize>;
+    type One = isize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        5
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> isize {
+        part_one(program)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::parse_input;
+
+    #[test]
+    fn test_real_input() {
+        let program = parse_input(&Day5);
+        assert_eq!(Day5.part_one(&program), 16_225_258);
+        assert_eq!(Day5.part_two(&program), 2_808_771);
+    }
+}
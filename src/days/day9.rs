@@ -0,0 +1,63 @@
+use crate::debug_println;
+use crate::solution::Solution;
+use crate::vm::{parse_program, VM};
+
+fn part_two(input: &[isize]) -> isize {
+    // For part 2, the input value should be 2
+    let mut vm = VM::new(input.to_owned());
+    vm.push_input(2);
+    debug_println!("{:?}", vm);
+    vm.run();
+    vm.pop_output().expect("Sensor boost gave no coordinates")
+}
+
+fn part_one(input: &[isize]) -> isize {
+    // For part 1, the input value should be 1
+    let mut vm = VM::new(input.to_owned());
+    vm.push_input(1);
+    debug_println!("{:?}", vm);
+    vm.run();
+    vm.pop_output().expect("BOOST gave no keycode")
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<isize>;
+    type One = isize;
+    type Two = isize;
+
+    fn day(&self) -> u32 {
+        9
+    }
+
+    fn parse(&self, input: &str) -> Vec<isize> {
+        parse_program(input)
+    }
+
+    fn part_one(&self, program: &Vec<isize>) -> isize {
+        part_one(program)
+    }
+
+    fn part_two(&self, program: &Vec<isize>) -> isize {
+        part_two(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solution::parse_input;
+
+    #[test]
+    fn test_real_input() {
+        let program = parse_input(&Day9);
+        assert_eq!(Day9.part_one(&program), 3_518_157_894);
+    }
+
+    #[test]
+    fn test_real_input_part_two() {
+        let program = parse_input(&Day9);
+        assert_eq!(Day9.part_two(&program), 80379);
+    }
+}
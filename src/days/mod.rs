@@ -0,0 +1,44 @@
+/*
+
+Every day solved so far.  Each has a Solution (DayN) and its own binary in src/bin, and the aoc
+binary can run any or all of them through `all` and `get`.
+
+*/
+
+use crate::solution::Runner;
+
+pub mod day1;
+pub mod day11;
+pub mod day13;
+pub mod day15;
+pub mod day17;
+pub mod day19;
+pub mod day2;
+pub mod day21;
+pub mod day25;
+pub mod day5;
+pub mod day9;
+
+/// Every day, in calendar order, with their default options
+#[must_use]
+pub fn all() -> Vec<Box<dyn Runner>> {
+    vec![
+        Box::new(day1::Day1),
+        Box::new(day2::Day2),
+        Box::new(day5::Day5),
+        Box::new(day9::Day9),
+        Box::new(day11::Day11::default()),
+        Box::new(day13::Day13::default()),
+        Box::new(day15::Day15::default()),
+        Box::new(day17::Day17),
+        Box::new(day19::Day19::default()),
+        Box::new(day21::Day21),
+        Box::new(day25::Day25),
+    ]
+}
+
+/// The solution for `day`, if there is one
+#[must_use]
+pub fn get(day: u32) -> Option<Box<dyn Runner>> {
+    all().into_iter().find(|solution| solution.day() == day)
+}
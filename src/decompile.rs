@@ -0,0 +1,836 @@
+/*
+
+Turn an Intcode program into something closer to readable pseudocode.
+
+Everything here leans on the idioms the Intcode compilers behind the puzzle inputs use:
+
+* Calls store the return address (the address just after the jump) on the stack at `[rb+0]`,
+  arguments at `[rb+1]`, `[rb+2]`... and then do an unconditional jump, e.g.
+  `21101,37,0,0` `1105,1,578`.
+* Functions open with `ARB n` to allocate their frame, so arguments end up at negative offsets,
+  and close with `ARB -n` then jump through `[rb+0]`.
+* Arrays are indexed by patching the address operand of a later instruction, so any operand
+  cell that the program itself writes to is shown as an indirect access.
+
+Control flow is structured on the assumption that code is laid out in address order, which the
+compilers are good about.  Anything that doesn't fit the loop/if shapes falls back to a goto.
+
+*/
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cfg::{BasicBlock, Cfg, Target, Terminator};
+use crate::disasm::{Instruction, Param};
+use crate::vm::OC;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CallSite {
+    /// Start of the block that ends with the call
+    pub block: usize,
+    pub callee: usize,
+    pub return_to: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub entry: usize,
+    pub blocks: BTreeSet<usize>,
+    /// Size of the stack frame allocated by the prologue `ARB`, if there is one
+    pub frame_size: isize,
+    /// Highest argument slot any caller fills in
+    pub args: usize,
+}
+
+impl Function {
+    #[must_use]
+    pub fn name(&self) -> String {
+        if self.entry == 0 {
+            "main".to_string()
+        } else {
+            format!("sub_{}", self.entry)
+        }
+    }
+}
+
+/// Value an ADD or MUL produces when both inputs are immediate
+fn constant_value(instruction: &Instruction) -> Option<isize> {
+    match (&instruction.opcode, &instruction.params[..]) {
+        (OC::Add, [Param::Immediate(a), Param::Immediate(b), _]) => a.checked_add(*b),
+        (OC::Mul, [Param::Immediate(a), Param::Immediate(b), _]) => a.checked_mul(*b),
+        _ => None,
+    }
+}
+
+/// Index of the parameter an instruction writes to, if it writes at all
+fn written_param(instruction: &Instruction) -> Option<usize> {
+    match instruction.opcode {
+        OC::Add | OC::Mul | OC::LessThan | OC::Equals => Some(2),
+        OC::Input => Some(0),
+        _ => None,
+    }
+}
+
+/// A block is a call if it ends in an unconditional jump, having stashed the address right after
+/// that jump somewhere first.
+fn call_site(block: &BasicBlock) -> Option<CallSite> {
+    let Terminator::Jump(Target::Resolved(callee)) = block.terminator else {
+        return None;
+    };
+    let return_to = block.end();
+    block
+        .instructions
+        .iter()
+        .any(|i| constant_value(i) == Some(return_to as isize))
+        .then_some(CallSite {
+            block: block.start,
+            callee,
+            return_to,
+        })
+}
+
+/// Build the CFG, repeatedly adding the return address of every call we find as an entry point
+/// since nothing else statically reaches them.
+#[must_use]
+pub fn discover(memory: &[isize]) -> (Cfg, BTreeMap<usize, CallSite>) {
+    let mut entries: BTreeSet<usize> = BTreeSet::from([0]);
+    loop {
+        let cfg = Cfg::with_entries(memory, &entries.iter().copied().collect::<Vec<_>>());
+        let calls: BTreeMap<usize, CallSite> = cfg
+            .blocks
+            .values()
+            .filter_map(call_site)
+            .map(|call| (call.block, call))
+            .collect();
+        let before = entries.len();
+        entries.extend(calls.values().map(|call| call.return_to));
+        if entries.len() == before {
+            return (cfg, calls);
+        }
+    }
+}
+
+/// Successors within the same function: calls continue at their return address.
+fn intra_successors(block: &BasicBlock, calls: &BTreeMap<usize, CallSite>) -> Vec<usize> {
+    match calls.get(&block.start) {
+        Some(call) => vec![call.return_to],
+        None => block.successors(),
+    }
+}
+
+/// Split the program into functions: `main` at 0, plus anything that gets called.
+#[must_use]
+pub fn find_functions(cfg: &Cfg, calls: &BTreeMap<usize, CallSite>) -> BTreeMap<usize, Function> {
+    let entries: BTreeSet<usize> = std::iter::once(0)
+        .chain(calls.values().map(|call| call.callee))
+        .filter(|entry| cfg.blocks.contains_key(entry))
+        .collect();
+
+    let mut functions = BTreeMap::new();
+    for &entry in &entries {
+        let mut blocks = BTreeSet::new();
+        let mut to_visit = vec![entry];
+        while let Some(start) = to_visit.pop() {
+            // Jumping into another function's entry is a tail call, not part of this function
+            if (start != entry && entries.contains(&start)) || !blocks.insert(start) {
+                continue;
+            }
+            if let Some(block) = cfg.blocks.get(&start) {
+                to_visit.extend(intra_successors(block, calls));
+            }
+        }
+        // main usually sets up the stack after some checks, rather than right at the start
+        let frame_size = blocks
+            .iter()
+            .flat_map(|start| cfg.blocks[start].instructions.iter())
+            .find_map(|i| match (&i.opcode, i.params.first()) {
+                (OC::RelativeBaseOffset, Some(Param::Immediate(size))) => Some(*size),
+                _ => None,
+            })
+            .unwrap_or(0);
+        functions.insert(
+            entry,
+            Function {
+                entry,
+                blocks,
+                frame_size,
+                args: 0,
+            },
+        );
+    }
+
+    // Count arguments from what the callers set up before jumping
+    for call in calls.values() {
+        let args = call_arguments(&cfg.blocks[&call.block])
+            .keys()
+            .copied()
+            .max()
+            .unwrap_or(0);
+        if let Some(function) = functions.get_mut(&call.callee) {
+            function.args = function.args.max(args as usize);
+        }
+    }
+    functions
+}
+
+/// The trailing run of writes to `[rb+n]` before a call's jump, keyed by n.  Slot 0 is the return
+/// address, the rest are arguments.
+fn call_arguments(block: &BasicBlock) -> BTreeMap<isize, &Instruction> {
+    let mut args = BTreeMap::new();
+    for instruction in block.instructions.iter().rev().skip(1) {
+        match (&instruction.opcode, instruction.params.get(2)) {
+            (OC::Add | OC::Mul, Some(Param::Relative(slot))) if *slot >= 0 => {
+                args.entry(*slot).or_insert(instruction);
+            }
+            _ => break,
+        }
+    }
+    args.remove(&0);
+    args
+}
+
+/// Relative base at the start of each block, as an offset from the function's entry.  None means
+/// it was adjusted by something we can't follow statically.
+fn relative_base_deltas(
+    cfg: &Cfg,
+    function: &Function,
+    calls: &BTreeMap<usize, CallSite>,
+) -> BTreeMap<usize, Option<isize>> {
+    let mut deltas = BTreeMap::from([(function.entry, Some(0))]);
+    let mut to_visit = vec![function.entry];
+    while let Some(start) = to_visit.pop() {
+        let block = &cfg.blocks[&start];
+        let mut delta = deltas[&start];
+        for instruction in &block.instructions {
+            delta = adjust_delta(delta, instruction);
+        }
+        for next in intra_successors(block, calls) {
+            if !function.blocks.contains(&next) {
+                continue;
+            }
+            let merged = match deltas.get(&next) {
+                None => delta,
+                Some(existing) if *existing == delta => continue,
+                Some(_) => None,
+            };
+            if deltas.get(&next) != Some(&merged) {
+                deltas.insert(next, merged);
+                to_visit.push(next);
+            }
+        }
+    }
+    deltas
+}
+
+fn adjust_delta(delta: Option<isize>, instruction: &Instruction) -> Option<isize> {
+    match (&instruction.opcode, instruction.params.first()) {
+        (OC::RelativeBaseOffset, Some(Param::Immediate(n))) => delta.map(|d| d + n),
+        (OC::RelativeBaseOffset, _) => None,
+        _ => delta,
+    }
+}
+
+/// Operand cells that some instruction writes to, i.e. self-modified addresses
+fn patched_cells(cfg: &Cfg) -> BTreeSet<usize> {
+    let instructions: Vec<&Instruction> = cfg
+        .blocks
+        .values()
+        .flat_map(|block| block.instructions.iter())
+        .collect();
+    let operand_cells: BTreeSet<usize> = instructions
+        .iter()
+        .flat_map(|i| i.address + 1..i.next_address())
+        .collect();
+    instructions
+        .iter()
+        .filter_map(|i| match written_param(i).map(|index| &i.params[index]) {
+            Some(Param::Position(address)) if *address >= 0 => Some(*address as usize),
+            _ => None,
+        })
+        .filter(|address| operand_cells.contains(address))
+        .collect()
+}
+
+enum Line {
+    Code(usize, String),
+    Label(usize, usize),
+}
+
+struct Loop {
+    header: usize,
+    exit: usize,
+    latch: usize,
+    latch_condition: Option<String>,
+    uses_continue: bool,
+}
+
+struct Emitter<'a> {
+    cfg: &'a Cfg,
+    function: &'a Function,
+    functions: &'a BTreeMap<usize, Function>,
+    calls: &'a BTreeMap<usize, CallSite>,
+    deltas: BTreeMap<usize, Option<isize>>,
+    patched: &'a BTreeSet<usize>,
+    lines: Vec<Line>,
+    gotos: BTreeSet<usize>,
+    loops: Vec<Loop>,
+    locals: BTreeSet<isize>,
+}
+
+impl Emitter<'_> {
+    fn slot_name(&mut self, slot: isize) -> String {
+        let args = self.function.args as isize;
+        if slot < 0 {
+            format!("caller[{slot}]")
+        } else if slot == 0 && self.function.entry != 0 {
+            "return_address".to_string()
+        } else if slot >= 1 && slot <= args {
+            format!("arg{slot}")
+        } else if slot < self.function.frame_size {
+            self.locals.insert(slot - args);
+            format!("local{}", slot - args)
+        } else {
+            format!("out{}", slot - self.function.frame_size)
+        }
+    }
+
+    fn operand(&mut self, instruction: &Instruction, index: usize, delta: Option<isize>) -> String {
+        let cell = instruction.address + 1 + index;
+        let patched = self.patched.contains(&cell);
+        match instruction.params[index] {
+            Param::Immediate(_) if patched => format!("mem[{cell}]"),
+            Param::Immediate(value) => value.to_string(),
+            Param::Position(_) if patched => format!("mem[mem[{cell}]]"),
+            Param::Position(address) => format!("mem[{address}]"),
+            Param::Relative(_) if patched => format!("rb[mem[{cell}]]"),
+            Param::Relative(offset) => match delta {
+                Some(delta) => self.slot_name(delta + offset),
+                None => format!("rb[{offset}]"),
+            },
+        }
+    }
+
+    /// Pseudocode for a non-control-flow instruction
+    fn statement(&mut self, instruction: &Instruction, delta: Option<isize>) -> Option<String> {
+        let mut operand = |index| self.operand(instruction, index, delta);
+        let statement = match instruction.opcode {
+            OC::Add | OC::Mul | OC::LessThan | OC::Equals => {
+                let (a, b, destination) = (operand(0), operand(1), operand(2));
+                let expression = match instruction.opcode {
+                    OC::Add if b == "0" => a,
+                    OC::Add if a == "0" => b,
+                    OC::Add if b.starts_with('-') => format!("{a} - {}", &b[1..]),
+                    OC::Add => format!("{a} + {b}"),
+                    OC::Mul if b == "1" => a,
+                    OC::Mul if a == "1" => b,
+                    OC::Mul if b == "-1" => format!("-{a}"),
+                    OC::Mul if a == "-1" => format!("-{b}"),
+                    OC::Mul => format!("{a} * {b}"),
+                    OC::LessThan => format!("{a} < {b}"),
+                    _ => format!("{a} == {b}"),
+                };
+                format!("{destination} = {expression};")
+            }
+            OC::Input => format!("{} = input();", operand(0)),
+            OC::Output => format!("output({});", operand(0)),
+            // Frame setup and teardown is implied by the function and its locals
+            OC::RelativeBaseOffset
+                if delta.is_some() && matches!(instruction.params[0], Param::Immediate(_)) =>
+            {
+                return None
+            }
+            OC::RelativeBaseOffset => format!("rb += {};", operand(0)),
+            // Jumps with constant conditions that are never taken
+            OC::JumpIfTrue | OC::JumpIfFalse | OC::End => return None,
+        };
+        Some(statement)
+    }
+
+    /// Condition under which a conditional jump is taken
+    fn condition(
+        &mut self,
+        instruction: &Instruction,
+        delta: Option<isize>,
+        negate: bool,
+    ) -> String {
+        let value = self.operand(instruction, 0, delta);
+        if (instruction.opcode == OC::JumpIfFalse) != negate {
+            format!("!{value}")
+        } else {
+            value
+        }
+    }
+
+    fn push(&mut self, indent: usize, code: String) {
+        self.lines.push(Line::Code(indent, code));
+    }
+
+    /// How to get to `target` when control would otherwise continue at `next`
+    fn jump_to(&mut self, target: usize, next: usize) -> Option<String> {
+        if target == next {
+            return None;
+        }
+        if let Some(current) = self.loops.last_mut() {
+            if target == current.header {
+                current.uses_continue = true;
+                return Some("continue;".to_string());
+            }
+            if target == current.exit {
+                return Some("break;".to_string());
+            }
+        }
+        if target != self.function.entry {
+            if let Some(function) = self.functions.get(&target) {
+                return Some(format!("goto {};", function.name()));
+            }
+        }
+        self.gotos.insert(target);
+        Some(format!("goto label_{target};"))
+    }
+
+    fn is_loop_header(&self, start: usize) -> bool {
+        self.loops.iter().any(|current| current.header == start)
+    }
+
+    /// Emit the blocks of this function in [lo, hi).  `follow` is where control goes when the last
+    /// of them falls off the end.
+    fn emit_range(&mut self, lo: usize, hi: usize, follow: usize, indent: usize) {
+        let starts: Vec<usize> = self.function.blocks.range(lo..hi).copied().collect();
+        let mut index = 0;
+        while index < starts.len() {
+            let start = starts[index];
+            let next = starts.get(index + 1).copied().unwrap_or(follow);
+            self.lines.push(Line::Label(indent, start));
+            let resume_at = if self.is_loop_header(start) {
+                None
+            } else {
+                self.try_emit_loop(&starts[index..], hi, indent)
+            };
+            let resume_at =
+                resume_at.or_else(|| self.emit_block(&starts[index..], hi, next, follow, indent));
+            index = match resume_at {
+                Some(resume_at) => starts
+                    .iter()
+                    .position(|s| *s >= resume_at)
+                    .unwrap_or(starts.len()),
+                None => index + 1,
+            };
+        }
+    }
+
+    /// If `starts[0]` is the target of a back edge, emit the loop and return where to carry on.
+    fn try_emit_loop(&mut self, starts: &[usize], hi: usize, indent: usize) -> Option<usize> {
+        let cfg = self.cfg;
+        let header = starts[0];
+        let latch = starts.iter().rev().copied().find(|s| {
+            let block = &cfg.blocks[s];
+            !self.calls.contains_key(s)
+                && matches!(
+                    block.terminator,
+                    Terminator::Jump(Target::Resolved(t)) | Terminator::Branch { target: Target::Resolved(t), .. } if t == header
+                )
+        })?;
+        let latch_block = &cfg.blocks[&latch];
+        let exit = latch_block.end();
+        if exit > hi {
+            return None;
+        }
+        let header_block = &cfg.blocks[&header];
+        let delta = self.deltas.get(&header).copied().flatten();
+
+        let mut outer_lines = std::mem::take(&mut self.lines);
+        self.loops.push(Loop {
+            header,
+            exit,
+            latch,
+            latch_condition: None,
+            uses_continue: false,
+        });
+
+        // `while (cond) { ... }` when the header does nothing but test for the exit
+        let while_condition = match header_block.terminator {
+            Terminator::Branch {
+                target: Target::Resolved(target),
+                ..
+            } if target == exit
+                && header_block.instructions.len() == 1
+                && matches!(latch_block.terminator, Terminator::Jump(_)) =>
+            {
+                let condition = self.condition(&header_block.instructions[0], delta, true);
+                Some(condition)
+            }
+            _ => None,
+        };
+        let body_start = if while_condition.is_some() {
+            header_block.end()
+        } else {
+            header
+        };
+        self.emit_range(body_start, exit, header, indent + 1);
+
+        let current = self.loops.pop().unwrap();
+        let body = std::mem::take(&mut self.lines);
+        if let Some(condition) = while_condition {
+            outer_lines.push(Line::Code(indent, format!("while ({condition}) {{")));
+            outer_lines.extend(body);
+            outer_lines.push(Line::Code(indent, "}".to_string()));
+        } else if let Some(condition) = current.latch_condition {
+            if current.uses_continue {
+                outer_lines.push(Line::Code(indent, "loop {".to_string()));
+                outer_lines.extend(body);
+                outer_lines.push(Line::Code(
+                    indent + 1,
+                    format!("if (!({condition})) break;"),
+                ));
+                outer_lines.push(Line::Code(indent, "}".to_string()));
+            } else {
+                outer_lines.push(Line::Code(indent, "do {".to_string()));
+                outer_lines.extend(body);
+                outer_lines.push(Line::Code(indent, format!("}} while ({condition});")));
+            }
+        } else {
+            outer_lines.push(Line::Code(indent, "loop {".to_string()));
+            outer_lines.extend(body);
+            outer_lines.push(Line::Code(indent, "}".to_string()));
+        }
+        self.lines = outer_lines;
+        Some(exit)
+    }
+
+    /// Emit `starts[0]` and, for if/else shapes, the blocks it governs.  Returns where to carry on
+    /// if that's anywhere other than the next block.
+    fn emit_block(
+        &mut self,
+        starts: &[usize],
+        hi: usize,
+        next: usize,
+        follow: usize,
+        indent: usize,
+    ) -> Option<usize> {
+        let (cfg, calls) = (self.cfg, self.calls);
+        let block = &cfg.blocks[&starts[0]];
+        let call = calls.get(&block.start);
+        let arguments = call.map(|_| call_arguments(block)).unwrap_or_default();
+        let skipped: usize = if call.is_some() {
+            // the return address is written somewhere in the trailing run too
+            block
+                .instructions
+                .iter()
+                .rev()
+                .skip(1)
+                .take_while(|i| {
+                    matches!((&i.opcode, i.params.get(2)), (OC::Add | OC::Mul, Some(Param::Relative(slot))) if *slot >= 0)
+                })
+                .count()
+        } else {
+            0
+        };
+        let ends_in_control_flow = matches!(
+            block.terminator,
+            Terminator::Jump(_) | Terminator::Branch { .. } | Terminator::Halt
+        );
+        let body_length = block.instructions.len() - usize::from(ends_in_control_flow) - skipped;
+
+        let mut delta = self.deltas.get(&block.start).copied().flatten();
+        for instruction in &block.instructions[..body_length] {
+            if let Some(statement) = self.statement(instruction, delta) {
+                self.push(indent, statement);
+            }
+            delta = adjust_delta(delta, instruction);
+        }
+        let last = block.instructions.last();
+
+        match block.terminator {
+            Terminator::Fallthrough(target) => {
+                if let Some(jump) = self.jump_to(target, next) {
+                    self.push(indent, jump);
+                }
+            }
+            Terminator::Jump(Target::Resolved(target)) => {
+                if let Some(call) = call {
+                    let mut args = vec![];
+                    for slot in 1..=arguments.keys().copied().max().unwrap_or(0) {
+                        let arg = match arguments.get(&slot) {
+                            Some(instruction) => {
+                                let a = self.operand(instruction, 0, delta);
+                                let b = self.operand(instruction, 1, delta);
+                                match (&instruction.opcode, a.as_str(), b.as_str()) {
+                                    (OC::Add, _, "0") | (OC::Mul, _, "1") => a,
+                                    (OC::Add, "0", _) | (OC::Mul, "1", _) => b,
+                                    (OC::Add, _, _) => format!("{a} + {b}"),
+                                    _ => format!("{a} * {b}"),
+                                }
+                            }
+                            None => "_".to_string(),
+                        };
+                        args.push(arg);
+                    }
+                    let name = self
+                        .functions
+                        .get(&call.callee)
+                        .map_or(format!("sub_{}", call.callee), Function::name);
+                    self.push(indent, format!("{name}({});", args.join(", ")));
+                    if let Some(jump) = self.jump_to(call.return_to, next) {
+                        self.push(indent, jump);
+                    }
+                } else if let Some(jump) = self.jump_to(target, next) {
+                    self.push(indent, jump);
+                }
+            }
+            Terminator::Jump(Target::Unresolved) => {
+                let instruction = last.unwrap();
+                let statement = match (instruction.params[1], delta) {
+                    (Param::Relative(offset), Some(delta)) if delta + offset == 0 => {
+                        "return;".to_string()
+                    }
+                    _ => format!("goto *{};", self.operand(instruction, 1, delta)),
+                };
+                self.push(indent, statement);
+            }
+            Terminator::Branch {
+                target,
+                fallthrough,
+            } => {
+                let instruction = last.unwrap();
+                if let Some(current) = self.loops.last() {
+                    if current.latch == block.start
+                        && target == Target::Resolved(current.header)
+                        && fallthrough == current.exit
+                    {
+                        let condition = self.condition(instruction, delta, false);
+                        self.loops.last_mut().unwrap().latch_condition = Some(condition);
+                        return None;
+                    }
+                }
+                match target {
+                    Target::Resolved(target)
+                        if target > fallthrough
+                            && ((target < hi && self.function.blocks.contains(&target))
+                                || target == follow)
+                            && starts.get(1) == Some(&fallthrough) =>
+                    {
+                        return Some(self.emit_if(
+                            starts,
+                            target,
+                            hi,
+                            follow,
+                            indent,
+                            instruction,
+                            delta,
+                        ));
+                    }
+                    Target::Resolved(target) => {
+                        let condition = self.condition(instruction, delta, false);
+                        if let Some(jump) = self.jump_to(target, fallthrough) {
+                            self.push(indent, format!("if ({condition}) {jump}"));
+                        }
+                    }
+                    Target::Unresolved => {
+                        let condition = self.condition(instruction, delta, false);
+                        let target = self.operand(instruction, 1, delta);
+                        self.push(indent, format!("if ({condition}) goto *{target};"));
+                    }
+                }
+                if let Some(jump) = self.jump_to(fallthrough, next) {
+                    self.push(indent, jump);
+                }
+            }
+            Terminator::Halt => self.push(indent, "halt();".to_string()),
+            Terminator::Invalid => {
+                let address = block.end();
+                self.push(indent, format!("invalid(); // can't decode mem[{address}]"));
+            }
+        }
+        None
+    }
+
+    /// `if (!cond) { fallthrough..target } [else { target..end }]`
+    #[allow(clippy::too_many_arguments)]
+    fn emit_if(
+        &mut self,
+        starts: &[usize],
+        target: usize,
+        hi: usize,
+        follow: usize,
+        indent: usize,
+        instruction: &Instruction,
+        delta: Option<isize>,
+    ) -> usize {
+        let condition = self.condition(instruction, delta, true);
+        let then_start = starts[1];
+
+        // An unconditional jump at the end of the "then" part, over a run of blocks that start
+        // at the branch target, is an else.
+        let then_last = starts.iter().copied().take_while(|s| *s < target).last();
+        let else_end = then_last.and_then(|last| match self.cfg.blocks[&last].terminator {
+            Terminator::Jump(Target::Resolved(end))
+                if !self.calls.contains_key(&last)
+                    && end > target
+                    && (end < hi || end == follow)
+                    && self.function.blocks.contains(&target) =>
+            {
+                Some(end)
+            }
+            _ => None,
+        });
+
+        self.push(indent, format!("if ({condition}) {{"));
+        match else_end {
+            Some(end) => {
+                self.emit_range(then_start, target, end, indent + 1);
+                self.push(indent, "} else {".to_string());
+                self.emit_range(target, end, end, indent + 1);
+                self.push(indent, "}".to_string());
+                end
+            }
+            None => {
+                self.emit_range(then_start, target, target, indent + 1);
+                self.push(indent, "}".to_string());
+                target
+            }
+        }
+    }
+}
+
+/// Decompile a whole program into pseudocode.
+#[must_use]
+pub fn decompile(memory: &[isize]) -> String {
+    let (cfg, calls) = discover(memory);
+    let functions = find_functions(&cfg, &calls);
+    let patched = patched_cells(&cfg);
+
+    let mut output = String::new();
+    for function in functions.values() {
+        let mut emitter = Emitter {
+            cfg: &cfg,
+            function,
+            functions: &functions,
+            calls: &calls,
+            deltas: relative_base_deltas(&cfg, function, &calls),
+            patched: &patched,
+            lines: vec![],
+            gotos: BTreeSet::new(),
+            loops: vec![],
+            locals: BTreeSet::new(),
+        };
+        emitter.emit_range(0, usize::MAX, usize::MAX, 1);
+
+        let args: Vec<String> = (1..=function.args).map(|n| format!("arg{n}")).collect();
+        output.push_str(&format!("fn {}({}) {{\n", function.name(), args.join(", ")));
+        if !emitter.locals.is_empty() {
+            let locals: Vec<String> = emitter.locals.iter().map(|n| format!("local{n}")).collect();
+            output.push_str(&format!("    var {};\n", locals.join(", ")));
+        }
+        for line in &emitter.lines {
+            match line {
+                Line::Code(indent, code) => {
+                    output.push_str(&"    ".repeat(*indent));
+                    output.push_str(code);
+                    output.push('\n');
+                }
+                Line::Label(indent, address) if emitter.gotos.contains(address) => {
+                    output.push_str(&"    ".repeat(indent - 1));
+                    output.push_str(&format!("label_{address}:\n"));
+                }
+                Line::Label(..) => {}
+            }
+        }
+        output.push_str("}\n\n");
+    }
+    output.pop();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_do_while() {
+        // day 9 quine
+        let program = decompile(&[
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ]);
+        assert_eq!(
+            program,
+            "fn main() {
+    do {
+        rb += 1;
+        output(rb[-1]);
+        mem[100] = mem[100] + 1;
+        mem[101] = mem[100] == 16;
+    } while (!mem[101]);
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_if_else() {
+        // output 1 if the input is 8, otherwise 0
+        let program = decompile(&[
+            3, 3, 1108, -1, 8, 3, 1005, 3, 15, 104, 0, 1105, 1, 17, 99, 104, 1, 99,
+        ]);
+        assert_eq!(
+            program,
+            "fn main() {
+    mem[3] = input();
+    mem[3] = mem[3] == 8;
+    if (!mem[3]) {
+        output(0);
+    } else {
+        output(1);
+    }
+    halt();
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_call_and_return() {
+        let memory = vec![
+            109, 100, // ARB 100: set up the stack
+            21101, 7, 0, 1, // first argument
+            21101, 13, 0, 0, // return address
+            1105, 1, 16, // call
+            204, 1,  // output the result
+            99, // halt
+            109, 2, // callee: 2 slot frame
+            22201, -1, -1, -1, // arg1 = arg1 + arg1
+            109, -2, // tear down the frame
+            2106, 0, 0, // return
+        ];
+        let (_, calls) = discover(&memory);
+        assert_eq!(
+            calls.values().collect::<Vec<_>>(),
+            vec![&CallSite {
+                block: 0,
+                callee: 16,
+                return_to: 13
+            }]
+        );
+        assert_eq!(
+            decompile(&memory),
+            "fn main() {
+    sub_16(7);
+    output(out1);
+    halt();
+}
+
+fn sub_16(arg1) {
+    arg1 = arg1 + arg1;
+    return;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_self_modified_operands_are_indirect() {
+        // Copy the input into the address operand of the output, so it prints mem[input]
+        let program = decompile(&[3, 3, 4, 0, 99]);
+        assert!(program.contains("mem[3] = input();"));
+        assert!(program.contains("output(mem[mem[3]]);"));
+    }
+}
@@ -0,0 +1,542 @@
+/*
+
+Differential testing: run the same program through two VMs in lockstep and stop at the first
+instruction after which they disagree about anything observable, including one of them stopping
+while the other carries on.
+
+"Two VMs" can be two implementations, or the same implementation configured differently (e.g.
+with self modification tracking on), so long as both implement Machine.
+
+When they do diverge, we replay both machines up to just before the instruction in question and
+cut the left one down to a minimal reproducer: that instruction, and the memory it reads, with
+every other address zeroed and anything past the last of them dropped.  Loading the reproducer
+into each machine and calling `step()` once is all it takes to see the difference again.  If
+the cut down version doesn't diverge (the difference depends on something else in memory), the
+reproducer is the whole of the left machine instead.
+
+*/
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::disasm::{decode, writes_to_param, Param};
+use crate::memory::Memory;
+use crate::vm::{Snapshot, VMError, VM};
+
+/// What the harness needs to drive a VM and look inside it
+pub trait Machine: Clone {
+    /// # Errors
+    ///
+    /// If the instruction is invalid
+    fn step(&mut self) -> Result<(), VMError>;
+    fn pointer(&self) -> usize;
+    fn relative_base(&self) -> isize;
+    fn memory(&self) -> &Memory;
+    fn output(&self) -> &VecDeque<isize>;
+    /// Inputs to be read in the order given, after anything already waiting
+    fn queue_input(&mut self, values: &[isize]);
+    fn finished(&self) -> bool;
+    fn needs_input(&self) -> bool;
+    fn snapshot(&self) -> Snapshot;
+    fn restore(&mut self, snapshot: &Snapshot);
+
+    /// Whether `VM::run` would stop here
+    fn stopped(&self) -> bool {
+        self.finished() || self.needs_input() || self.pointer() >= self.memory().len()
+    }
+}
+
+impl Machine for VM {
+    fn step(&mut self) -> Result<(), VMError> {
+        VM::step(self)
+    }
+
+    fn pointer(&self) -> usize {
+        VM::pointer(self)
+    }
+
+    fn relative_base(&self) -> isize {
+        VM::relative_base(self)
+    }
+
+    fn memory(&self) -> &Memory {
+        VM::memory(self)
+    }
+
+    fn output(&self) -> &VecDeque<isize> {
+        VM::output(self)
+    }
+
+    fn queue_input(&mut self, values: &[isize]) {
+        VM::queue_input(self, values.iter().copied());
+    }
+
+    fn finished(&self) -> bool {
+        VM::finished(self)
+    }
+
+    fn needs_input(&self) -> bool {
+        VM::needs_input(self)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        VM::snapshot(self)
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        VM::restore(self, snapshot);
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Difference {
+    Pointer(usize, usize),
+    RelativeBase(isize, isize),
+    /// Memory that only one side has grown into counts as zero on the other
+    Memory {
+        address: usize,
+        left: isize,
+        right: isize,
+    },
+    Output(Vec<isize>, Vec<isize>),
+    Finished(bool, bool),
+    NeedsInput(bool, bool),
+    /// One has stopped, as `Machine::stopped`, while the other would carry on
+    Stopped(bool, bool),
+    Error(Option<VMError>, Option<VMError>),
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::Pointer(left, right) => write!(f, "pointer: {left} vs {right}"),
+            Difference::RelativeBase(left, right) => {
+                write!(f, "relative base: {left} vs {right}")
+            }
+            Difference::Memory {
+                address,
+                left,
+                right,
+            } => write!(f, "mem[{address}]: {left} vs {right}"),
+            Difference::Output(left, right) => write!(f, "output: {left:?} vs {right:?}"),
+            Difference::Finished(left, right) => write!(f, "finished: {left} vs {right}"),
+            Difference::NeedsInput(left, right) => write!(f, "needs input: {left} vs {right}"),
+            Difference::Stopped(left, right) => write!(f, "stopped: {left} vs {right}"),
+            Difference::Error(left, right) => write!(f, "error: {left:?} vs {right:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// How many instructions both had executed successfully before this one
+    pub step: usize,
+    /// Address of the instruction that caused the divergence
+    pub address: usize,
+    pub differences: Vec<Difference>,
+    /// The left machine just before the offending instruction, cut down to as little as still
+    /// diverges
+    pub reproducer: Snapshot,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let instruction = decode(&self.reproducer.memory.to_vec(), self.address)
+            .map_or("???".to_string(), |i| i.to_string());
+        writeln!(
+            f,
+            "Diverged at step {}, instruction at {}: {}",
+            self.step, self.address, instruction
+        )?;
+        for difference in &self.differences {
+            writeln!(f, "  {difference}")?;
+        }
+        writeln!(
+            f,
+            "Reproducer: pointer {}, relative base {}, input {:?}",
+            self.reproducer.pointer, self.reproducer.relative_base, self.reproducer.input
+        )?;
+        let memory: Vec<String> = self
+            .reproducer
+            .memory
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        write!(f, "  memory: {}", memory.join(","))
+    }
+}
+
+/// How a lockstep run ended, when both sides agreed throughout
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Agreement {
+    Finished {
+        steps: usize,
+    },
+    NeedsInput {
+        steps: usize,
+    },
+    /// Both ran off the end of memory without halting
+    RanOffEnd {
+        steps: usize,
+    },
+    /// Both failed in the same way
+    Failed {
+        steps: usize,
+        error: VMError,
+    },
+    /// Hit `max_steps` without either side stopping
+    OutOfSteps,
+}
+
+fn compare<A: Machine, B: Machine>(left: &A, right: &B) -> Vec<Difference> {
+    let mut differences = vec![];
+    if left.pointer() != right.pointer() {
+        differences.push(Difference::Pointer(left.pointer(), right.pointer()));
+    }
+    if left.relative_base() != right.relative_base() {
+        differences.push(Difference::RelativeBase(
+            left.relative_base(),
+            right.relative_base(),
+        ));
+    }
+    let (left_memory, right_memory) = (left.memory(), right.memory());
+    for address in 0..left_memory.len().max(right_memory.len()) {
+        let left = left_memory.get(address).copied().unwrap_or(0);
+        let right = right_memory.get(address).copied().unwrap_or(0);
+        if left != right {
+            differences.push(Difference::Memory {
+                address,
+                left,
+                right,
+            });
+        }
+    }
+    if left.output() != right.output() {
+        differences.push(Difference::Output(
+            left.output().iter().copied().collect(),
+            right.output().iter().copied().collect(),
+        ));
+    }
+    if left.finished() != right.finished() {
+        differences.push(Difference::Finished(left.finished(), right.finished()));
+    }
+    if left.needs_input() != right.needs_input() {
+        differences.push(Difference::NeedsInput(
+            left.needs_input(),
+            right.needs_input(),
+        ));
+    }
+    differences
+}
+
+// The instruction under the pointer and the addresses it reads, all else zeroed
+fn cut_down(snapshot: &Snapshot) -> Snapshot {
+    let memory = snapshot.memory.to_vec();
+    let mut needed = vec![snapshot.pointer];
+    if let Some(instruction) = decode(&memory, snapshot.pointer) {
+        needed.extend(instruction.span());
+        for (index, param) in instruction.params.iter().enumerate() {
+            if writes_to_param(&instruction.opcode, index) {
+                continue;
+            }
+            let address = match param {
+                Param::Position(address) => Some(*address),
+                Param::Relative(offset) => snapshot.relative_base.checked_add(*offset),
+                Param::Immediate(_) => None,
+            };
+            needed.extend(address.and_then(|address| usize::try_from(address).ok()));
+        }
+    }
+    needed.retain(|address| *address < memory.len());
+    let mut cells = vec![0; needed.iter().max().map_or(0, |last| last + 1)];
+    for address in needed {
+        cells[address] = memory[address];
+    }
+    let mut cut = snapshot.clone();
+    cut.memory = Memory::from(cells);
+    cut.output.clear();
+    cut
+}
+
+// The left machine as it is, cut down if that still diverges when both step from it
+fn reproducer<A: Machine, B: Machine>(left: &A, right: &B) -> Snapshot {
+    let whole = left.snapshot();
+    let cut = cut_down(&whole);
+    let (mut left, mut right) = (left.clone(), right.clone());
+    left.restore(&cut);
+    right.restore(&cut);
+    let (left_result, right_result) = (left.step(), right.step());
+    if left_result != right_result || !compare(&left, &right).is_empty() {
+        cut
+    } else {
+        whole
+    }
+}
+
+/// Run `left` and `right` one instruction at a time, feeding both the same `input` (first value
+/// read first), until they
+/// stop, disagree, or `max_steps` instructions have run.
+///
+/// # Errors
+///
+/// Returns the first divergence between the two machines.
+pub fn lockstep<A: Machine, B: Machine>(
+    mut left: A,
+    mut right: B,
+    input: &[isize],
+    max_steps: usize,
+) -> Result<Agreement, Box<Divergence>> {
+    left.queue_input(input);
+    right.queue_input(input);
+    let (left_start, right_start) = (left.clone(), right.clone());
+    let diverged = |step: usize, address: usize, differences: Vec<Difference>| {
+        let (mut left, mut right) = (left_start.clone(), right_start.clone());
+        for _ in 0..step {
+            // Every step up to here succeeded the first time round
+            let _ = left.step();
+            let _ = right.step();
+        }
+        Box::new(Divergence {
+            step,
+            address,
+            differences,
+            reproducer: reproducer(&left, &right),
+        })
+    };
+
+    for step in 0..max_steps {
+        if left.stopped() || right.stopped() {
+            // Rather than stepping one past its end to find out
+            let mut differences = compare(&left, &right);
+            if left.stopped() != right.stopped() {
+                differences.push(Difference::Stopped(left.stopped(), right.stopped()));
+            }
+            if !differences.is_empty() {
+                return Err(diverged(step, left.pointer(), differences));
+            }
+            return Ok(if left.finished() {
+                Agreement::Finished { steps: step }
+            } else if left.needs_input() {
+                Agreement::NeedsInput { steps: step }
+            } else {
+                Agreement::RanOffEnd { steps: step }
+            });
+        }
+        let address = left.pointer();
+        let left_result = left.step();
+        let right_result = right.step();
+        let mut differences = compare(&left, &right);
+        if left_result != right_result {
+            differences.push(Difference::Error(left_result.err(), right_result.err()));
+        } else if let (Err(error), true) = (left_result, differences.is_empty()) {
+            return Ok(Agreement::Failed { steps: step, error });
+        }
+        if !differences.is_empty() {
+            return Err(diverged(step, address, differences));
+        }
+    }
+    Ok(Agreement::OutOfSteps)
+}
+
+/// For tests: panic with the full divergence report if the machines disagree
+pub fn assert_lockstep<A: Machine, B: Machine>(left: A, right: B, input: &[isize]) -> Agreement {
+    match lockstep(left, right, input, 1_000_000) {
+        Ok(agreement) => agreement,
+        Err(divergence) => panic!("{divergence}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A deliberately broken VM, so we can check divergences are caught and reported properly
+    #[derive(Clone)]
+    struct OffByOne(VM);
+
+    impl Machine for OffByOne {
+        fn step(&mut self) -> Result<(), VMError> {
+            self.0.step()?;
+            // Wherever address 5 ends up holding 3, it gets 4 instead
+            if self.0.memory()[5] == 3 {
+                self.0.set_memory(5, 4);
+            }
+            Ok(())
+        }
+        fn pointer(&self) -> usize {
+            self.0.pointer()
+        }
+        fn relative_base(&self) -> isize {
+            self.0.relative_base()
+        }
+        fn memory(&self) -> &Memory {
+            self.0.memory()
+        }
+        fn output(&self) -> &VecDeque<isize> {
+            self.0.output()
+        }
+        fn queue_input(&mut self, values: &[isize]) {
+            self.0.queue_input(values.iter().copied());
+        }
+        fn finished(&self) -> bool {
+            self.0.finished()
+        }
+        fn needs_input(&self) -> bool {
+            self.0.needs_input()
+        }
+        fn snapshot(&self) -> Snapshot {
+            self.0.snapshot()
+        }
+        fn restore(&mut self, snapshot: &Snapshot) {
+            self.0.restore(snapshot);
+        }
+    }
+
+    #[test]
+    fn test_identical_machines_agree() {
+        let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let agreement = assert_lockstep(VM::new(program.clone()), VM::new(program), &[8]);
+        assert_eq!(agreement, Agreement::Finished { steps: 4 });
+    }
+
+    #[test]
+    fn test_waiting_for_input_agrees() {
+        let program = vec![3, 0, 99];
+        let agreement = assert_lockstep(VM::new(program.clone()), VM::new(program), &[]);
+        assert_eq!(agreement, Agreement::NeedsInput { steps: 1 });
+    }
+
+    #[test]
+    fn test_input_in_order() {
+        // Halts if it reads something smaller first, otherwise waits for more input
+        let program = vec![
+            3, 20, 3, 21, 7, 20, 21, 22, 1005, 22, 13, 3, 20, 99, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let agreement = assert_lockstep(VM::new(program.clone()), VM::new(program), &[1, 2]);
+        assert_eq!(agreement, Agreement::Finished { steps: 5 });
+    }
+
+    #[test]
+    fn test_running_off_the_end_agrees() {
+        // No 99, so it stops once it's past the add
+        let program = vec![1101, 1, 1, 3];
+        let agreement = assert_lockstep(VM::new(program.clone()), VM::new(program), &[]);
+        assert_eq!(agreement, Agreement::RanOffEnd { steps: 1 });
+    }
+
+    #[test]
+    fn test_both_failing_agrees() {
+        let program = vec![1101, 1, 1, 5, 42];
+        let agreement = assert_lockstep(VM::new(program.clone()), VM::new(program), &[]);
+        assert_eq!(
+            agreement,
+            Agreement::Failed {
+                steps: 1,
+                error: VMError::InvalidOpcode {
+                    address: 4,
+                    value: 42
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_out_of_steps() {
+        let program = vec![1105, 1, 0];
+        let result = lockstep(VM::new(program.clone()), VM::new(program), &[], 10);
+        assert_eq!(result.unwrap(), Agreement::OutOfSteps);
+    }
+
+    #[test]
+    fn test_divergence_reported() {
+        // One add that changes nothing, then the one that goes wrong
+        let program = vec![1101, 0, 0, 9, 1101, 1, 2, 5, 99, 0];
+        let divergence = lockstep(
+            VM::new(program.clone()),
+            OffByOne(VM::new(program.clone())),
+            &[],
+            100,
+        )
+        .unwrap_err();
+        assert_eq!(divergence.step, 1);
+        assert_eq!(divergence.address, 4);
+        assert_eq!(
+            divergence.differences,
+            vec![Difference::Memory {
+                address: 5,
+                left: 3,
+                right: 4
+            }]
+        );
+
+        // Loading the reproducer and stepping once is enough to see it again
+        assert_eq!(divergence.reproducer.pointer, 4);
+        assert_eq!(
+            divergence.reproducer.memory,
+            vec![0, 0, 0, 0, 1101, 1, 2, 5]
+        );
+        let again = lockstep(
+            VM::from(divergence.reproducer.clone()),
+            OffByOne(VM::from(divergence.reproducer.clone())),
+            &[],
+            100,
+        )
+        .unwrap_err();
+        assert_eq!(again.step, 0);
+        assert_eq!(again.differences, divergence.differences);
+
+        let report = divergence.to_string();
+        assert!(report.starts_with("Diverged at step 1, instruction at 4: ADD 1, 2, [5]\n"));
+        assert!(report.contains("  mem[5]: 3 vs 4\n"));
+        assert!(report.ends_with("  memory: 0,0,0,0,1101,1,2,5"));
+    }
+
+    #[test]
+    fn test_reproducer_keeps_what_is_read() {
+        // Adds [9] and [10] into 5, leaving out the 99 after it but keeping both of those
+        let program = vec![1101, 0, 0, 9, 1, 9, 10, 5, 99, 0, 3];
+        let divergence = lockstep(
+            VM::new(program.clone()),
+            OffByOne(VM::new(program)),
+            &[],
+            100,
+        )
+        .unwrap_err();
+        assert_eq!(
+            divergence.reproducer.memory,
+            vec![0, 0, 0, 0, 1, 9, 10, 5, 0, 0, 3]
+        );
+    }
+
+    #[test]
+    fn test_reproducer_falls_back_to_everything() {
+        // OffByOne only goes wrong because of the 3 already at 5, which the add doesn't read, so
+        // cutting that out would lose the divergence
+        let program = vec![1101, 1, 2, 9, 99, 3, 0, 0, 0, 0];
+        let divergence = lockstep(
+            VM::new(program.clone()),
+            OffByOne(VM::new(program.clone())),
+            &[],
+            100,
+        )
+        .unwrap_err();
+        assert_eq!(divergence.step, 0);
+        assert_eq!(divergence.reproducer.memory, program);
+    }
+
+    #[test]
+    fn test_one_stopping_diverges() {
+        // The same program, but the right one has a spare 0 on the end, so only the left has
+        // run off the end of its memory after the first instruction
+        let program = vec![1101, 0, 0, 3];
+        let mut longer = program.clone();
+        longer.push(0);
+        let divergence = lockstep(VM::new(program), VM::new(longer), &[], 100).unwrap_err();
+        assert_eq!(divergence.step, 1);
+        assert_eq!(divergence.address, 4);
+        assert_eq!(
+            divergence.differences,
+            vec![Difference::Stopped(true, false)]
+        );
+    }
+}
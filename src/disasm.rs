@@ -0,0 +1,187 @@
+/*
+
+Static decoding of Intcode memory into instructions, without running anything.
+
+The VM only ever decodes the instruction under the pointer, as it runs it, and an instruction it
+can't make sense of is an error (`VMError`) that stops the program.  For analysis we want to
+look at arbitrary addresses without running anything, many of which will turn out to be data,
+so everything here returns an Option instead.
+
+*/
+
+use std::fmt;
+
+use crate::vm::{try_decode_opcode, OC};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Param {
+    Position(isize),
+    Immediate(isize),
+    Relative(isize),
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Param::Position(address) => write!(f, "[{address}]"),
+            Param::Immediate(value) => write!(f, "{value}"),
+            Param::Relative(offset) if *offset < 0 => write!(f, "[rb{offset}]"),
+            Param::Relative(offset) => write!(f, "[rb+{offset}]"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instruction {
+    pub address: usize,
+    pub opcode: OC,
+    pub params: Vec<Param>,
+}
+
+impl Instruction {
+    /// Number of memory cells the instruction occupies, opcode included
+    #[must_use]
+    pub fn width(&self) -> usize {
+        1 + self.params.len()
+    }
+
+    /// Where execution continues if the instruction doesn't jump
+    #[must_use]
+    pub fn next_address(&self) -> usize {
+        self.address + self.width()
+    }
+
+    /// Addresses the instruction itself occupies
+    #[must_use]
+    pub fn span(&self) -> std::ops::Range<usize> {
+        self.address..self.next_address()
+    }
+
+    /// Back to the memory cells it came from
+    #[must_use]
+    pub fn encode(&self) -> Vec<isize> {
+        let mut opcode = self.opcode.code();
+        let mut encoded = vec![0];
+        for (index, param) in self.params.iter().enumerate() {
+            let (mode, value) = match param {
+                Param::Position(value) => (0, value),
+                Param::Immediate(value) => (1, value),
+                Param::Relative(value) => (2, value),
+            };
+            opcode += mode * 100 * 10isize.pow(index as u32);
+            encoded.push(*value);
+        }
+        encoded[0] = opcode;
+        encoded
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic())?;
+        for (index, param) in self.params.iter().enumerate() {
+            if index == 0 {
+                write!(f, " {param}")?;
+            } else {
+                write!(f, ", {param}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which of an opcode's parameters are written to, and so can't be immediate
+pub(crate) fn writes_to_param(opcode: &OC, index: usize) -> bool {
+    match opcode {
+        OC::Add | OC::Mul | OC::LessThan | OC::Equals => index == 2,
+        OC::Input => index == 0,
+        _ => false,
+    }
+}
+
+/// Decode the instruction starting at `address`.
+///
+/// Returns None if the cell isn't a valid opcode, uses an invalid parameter mode (including
+/// immediate mode for a write), or the parameters run off the end of `memory`.
+#[must_use]
+pub fn decode(memory: &[isize], address: usize) -> Option<Instruction> {
+    let raw = *memory.get(address)?;
+    let opcode = try_decode_opcode(raw)?;
+    let mut params = Vec::with_capacity(opcode.parameter_count());
+    for index in 0..opcode.parameter_count() {
+        let value = *memory.get(address + 1 + index)?;
+        let mode = (raw / (100 * 10isize.pow(index as u32))) % 10;
+        let param = match mode {
+            0 => Param::Position(value),
+            1 if !writes_to_param(&opcode, index) => Param::Immediate(value),
+            2 => Param::Relative(value),
+            _ => return None,
+        };
+        params.push(param);
+    }
+    Some(Instruction {
+        address,
+        opcode,
+        params,
+    })
+}
+
+/// Linear listing of the whole of memory, one instruction per line.  Anything that doesn't
+/// decode, or wouldn't encode back to exactly the same cells (e.g. junk in unused mode digits),
+/// is shown as DATA, so `asm::assemble` on the output gives back the original memory.
+#[must_use]
+pub fn disassemble(memory: &[isize]) -> String {
+    let mut listing = String::new();
+    let mut address = 0;
+    while address < memory.len() {
+        match decode(memory, address) {
+            Some(instruction) if instruction.encode() == memory[instruction.span()] => {
+                listing.push_str(&format!("{address:>5}: {instruction}\n"));
+                address = instruction.next_address();
+            }
+            _ => {
+                listing.push_str(&format!("{address:>5}: DATA {}\n", memory[address]));
+                address += 1;
+            }
+        }
+    }
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(vec![1, 9, 10, 3], "ADD [9], [10], [3]")]
+    #[case(vec![1002, 4, 3, 4], "MUL [4], 3, [4]")]
+    #[case(vec![109, 19], "ARB 19")]
+    #[case(vec![204, -34], "OUT [rb-34]")]
+    #[case(vec![21101, 37, 0, 0], "ADD 37, 0, [rb+0]")]
+    #[case(vec![1105, 1, 578], "JNZ 1, 578")]
+    #[case(vec![99], "HALT")]
+    fn test_decode_display(#[case] memory: Vec<isize>, #[case] expected: &str) {
+        let instruction = decode(&memory, 0).unwrap();
+        assert_eq!(instruction.to_string(), expected);
+        assert_eq!(instruction.next_address(), memory.len());
+    }
+
+    #[rstest]
+    #[case(vec![42])] // not an opcode
+    #[case(vec![-1])] // negative numbers aren't either
+    #[case(vec![1101, 1, 2])] // runs off the end of memory
+    #[case(vec![11101, 1, 2, 3])] // writes in immediate mode
+    #[case(vec![301, 1, 2, 3])] // mode 3 doesn't exist
+    fn test_decode_invalid(#[case] memory: Vec<isize>) {
+        assert_eq!(decode(&memory, 0), None);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(
+            disassemble(&[100001, 99, 1002, 4, 3, 4, 33]),
+            "    0: DATA 100001\n    1: HALT\n    2: MUL [4], 3, [4]\n    6: DATA 33\n"
+        );
+    }
+}
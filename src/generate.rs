@@ -0,0 +1,99 @@
+/*
+
+proptest strategies for generating Intcode programs, shared by the property tests.
+
+*/
+
+use proptest::prelude::*;
+
+use crate::disasm::{writes_to_param, Instruction, Param};
+use crate::vm::OC;
+
+fn opcode() -> impl Strategy<Value = OC> {
+    prop_oneof![
+        Just(OC::Add),
+        Just(OC::Mul),
+        Just(OC::Input),
+        Just(OC::Output),
+        Just(OC::JumpIfTrue),
+        Just(OC::JumpIfFalse),
+        Just(OC::LessThan),
+        Just(OC::Equals),
+        Just(OC::RelativeBaseOffset),
+    ]
+}
+
+/// Parameters that stay near the program, so they mostly touch memory it actually uses
+fn param(program_length: usize, written: bool) -> BoxedStrategy<Param> {
+    let position = (0..program_length as isize + 8).prop_map(Param::Position);
+    let relative = (-4isize..8).prop_map(Param::Relative);
+    if written {
+        prop_oneof![position, relative].boxed()
+    } else {
+        let immediate = (-10isize..100).prop_map(Param::Immediate);
+        prop_oneof![position, immediate, relative].boxed()
+    }
+}
+
+/// Programs made entirely of valid instructions, ending in HALT.  Jumps always go to the start
+/// of an instruction.  They can still fail at run time, e.g. by going negative through the
+/// relative base, or run forever.
+pub fn well_formed_program() -> impl Strategy<Value = Vec<isize>> {
+    prop::collection::vec(opcode(), 1..24)
+        .prop_flat_map(|opcodes| {
+            let mut starts = vec![];
+            let mut length = 0;
+            for opcode in &opcodes {
+                starts.push(length);
+                length += 1 + opcode.parameter_count();
+            }
+            starts.push(length);
+            let program_length = length + 1;
+
+            let params: Vec<BoxedStrategy<Vec<Param>>> = opcodes
+                .iter()
+                .map(|opcode| {
+                    let mut params = vec![];
+                    for index in 0..opcode.parameter_count() {
+                        let is_jump = matches!(opcode, OC::JumpIfTrue | OC::JumpIfFalse);
+                        if is_jump && index == 1 {
+                            let target = prop::sample::select(starts.clone())
+                                .prop_map(|start| Param::Immediate(start as isize));
+                            params.push(target.boxed());
+                        } else {
+                            params.push(param(program_length, writes_to_param(opcode, index)));
+                        }
+                    }
+                    params.boxed()
+                })
+                .collect();
+            (Just(opcodes), params)
+        })
+        .prop_map(|(opcodes, params)| {
+            let mut memory = vec![];
+            for (opcode, params) in opcodes.into_iter().zip(params) {
+                let instruction = Instruction {
+                    address: memory.len(),
+                    opcode,
+                    params,
+                };
+                memory.extend(instruction.encode());
+            }
+            memory.push(99);
+            memory
+        })
+}
+
+/// Arbitrary memory, which is almost always malformed somewhere.  Biased towards things that
+/// look like instructions so we get past the first cell reasonably often.
+pub fn any_memory() -> impl Strategy<Value = Vec<isize>> {
+    let instruction_like = (1isize..=9, 0isize..4, 0isize..4, 0isize..4)
+        .prop_map(|(opcode, a, b, c)| opcode + 100 * a + 1000 * b + 10000 * c);
+    let cell = prop_oneof![
+        3 => instruction_like,
+        1 => Just(99isize),
+        3 => -20isize..200,
+        1 => any::<isize>(),
+    ];
+    prop::collection::vec(cell, 0..64)
+}
@@ -0,0 +1,375 @@
+/*
+
+Points and directions, in two and three dimensions.
+
+Directions follow the maths convention that North is +y, as on day 11's hull.  Puzzles drawn on
+screen, with y growing downwards, flip the y of `delta()` themselves (or let a Grid do it).
+
+*/
+
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use num_traits::{PrimInt, Signed};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point<T: PrimInt + Display> {
+    pub x: T,
+    pub y: T,
+}
+
+// |a - b| without going below zero for unsigned types
+fn distance<T: PrimInt>(a: T, b: T) -> T {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+impl<T: PrimInt + Display> Point<T> {
+    /// Steps between the two points moving only North, South, East or West
+    #[must_use]
+    pub fn manhattan(&self, other: &Self) -> T {
+        distance(self.x, other.x) + distance(self.y, other.y)
+    }
+
+    /// Steps between the two points when diagonal moves are allowed too
+    #[must_use]
+    pub fn chebyshev(&self, other: &Self) -> T {
+        distance(self.x, other.x).max(distance(self.y, other.y))
+    }
+}
+
+impl<T: PrimInt + Display> AddAssign for Point<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        };
+    }
+}
+
+impl<T: PrimInt + Display> SubAssign for Point<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        };
+    }
+}
+
+impl<T: PrimInt + Display> Add for Point<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl<T: PrimInt + Display> Sub for Point<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl<T: PrimInt + Display> Mul<T> for Point<T> {
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self {
+        Point {
+            x: self.x * scalar,
+            y: self.y * scalar,
+        }
+    }
+}
+
+impl<T: PrimInt + Display + Signed> Neg for Point<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Point3<T: PrimInt + Display> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: PrimInt + Display> Point3<T> {
+    #[must_use]
+    pub fn manhattan(&self, other: &Self) -> T {
+        distance(self.x, other.x) + distance(self.y, other.y) + distance(self.z, other.z)
+    }
+
+    #[must_use]
+    pub fn chebyshev(&self, other: &Self) -> T {
+        distance(self.x, other.x)
+            .max(distance(self.y, other.y))
+            .max(distance(self.z, other.z))
+    }
+}
+
+impl<T: PrimInt + Display> AddAssign for Point3<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        };
+    }
+}
+
+impl<T: PrimInt + Display> SubAssign for Point3<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        };
+    }
+}
+
+impl<T: PrimInt + Display> Add for Point3<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Point3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl<T: PrimInt + Display> Sub for Point3<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Point3 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl<T: PrimInt + Display> Mul<T> for Point3<T> {
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self {
+        Point3 {
+            x: self.x * scalar,
+            y: self.y * scalar,
+            z: self.z * scalar,
+        }
+    }
+}
+
+impl<T: PrimInt + Display + Signed> Neg for Point3<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point3 {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Direction {
+    /// North, East, South and West, clockwise
+    pub const CARDINALS: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// All eight, clockwise from North
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    // Eighths of a turn clockwise from North
+    fn eighths(self) -> usize {
+        match self {
+            Direction::North => 0,
+            Direction::NorthEast => 1,
+            Direction::East => 2,
+            Direction::SouthEast => 3,
+            Direction::South => 4,
+            Direction::SouthWest => 5,
+            Direction::West => 6,
+            Direction::NorthWest => 7,
+        }
+    }
+
+    /// Turn clockwise by `eighths` of a full turn, or anticlockwise if it's negative
+    #[must_use]
+    pub fn rotate(&self, eighths: isize) -> Direction {
+        let turned = (self.eighths() as isize + eighths).rem_euclid(8);
+        Direction::ALL[turned as usize]
+    }
+
+    /// 90 degrees anticlockwise
+    #[must_use]
+    pub fn turn_left(&self) -> Direction {
+        self.rotate(-2)
+    }
+
+    /// 90 degrees clockwise
+    #[must_use]
+    pub fn turn_right(&self) -> Direction {
+        self.rotate(2)
+    }
+
+    #[must_use]
+    pub fn reverse(&self) -> Direction {
+        self.rotate(4)
+    }
+
+    #[must_use]
+    pub fn is_diagonal(&self) -> bool {
+        self.eighths() % 2 == 1
+    }
+
+    /// One step this way, with North as +y
+    #[must_use]
+    pub fn delta<T: PrimInt + Display + Signed>(&self) -> Point<T> {
+        let (one, zero) = (T::one(), T::zero());
+        let (x, y) = match self {
+            Direction::North => (zero, one),
+            Direction::South => (zero, -one),
+            Direction::East => (one, zero),
+            Direction::West => (-one, zero),
+            Direction::NorthEast => (one, one),
+            Direction::NorthWest => (-one, one),
+            Direction::SouthEast => (one, -one),
+            Direction::SouthWest => (-one, -one),
+        };
+        Point { x, y }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_arithmetic() {
+        let a = Point { x: 3, y: -2 };
+        let b = Point { x: -1, y: 5 };
+        assert_eq!(a + b, Point { x: 2, y: 3 });
+        assert_eq!(a - b, Point { x: 4, y: -7 });
+        assert_eq!(a * 3, Point { x: 9, y: -6 });
+        assert_eq!(-a, Point { x: -3, y: 2 });
+        let mut c = a;
+        c -= b;
+        c += Point { x: 1, y: 1 };
+        assert_eq!(c, Point { x: 5, y: -6 });
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = Point { x: 3, y: -2 };
+        let b = Point { x: -1, y: 5 };
+        assert_eq!(a.manhattan(&b), 11);
+        assert_eq!(a.chebyshev(&b), 7);
+        // Unsigned works too, whichever way round
+        let c: Point<u8> = Point { x: 1, y: 9 };
+        let d: Point<u8> = Point { x: 4, y: 2 };
+        assert_eq!(c.manhattan(&d), 10);
+        assert_eq!(d.chebyshev(&c), 7);
+    }
+
+    #[test]
+    fn test_point3() {
+        let a = Point3 { x: 1, y: 2, z: 3 };
+        let b = Point3 { x: -2, y: 0, z: 7 };
+        assert_eq!(a + b, Point3 { x: -1, y: 2, z: 10 });
+        assert_eq!(a - b, Point3 { x: 3, y: 2, z: -4 });
+        assert_eq!(
+            -(a * 2),
+            Point3 {
+                x: -2,
+                y: -4,
+                z: -6
+            }
+        );
+        assert_eq!(a.manhattan(&b), 9);
+        assert_eq!(a.chebyshev(&b), 4);
+    }
+
+    #[test]
+    fn test_rotation() {
+        for direction in Direction::ALL {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.reverse().reverse(), direction);
+            assert_eq!(direction.rotate(8), direction);
+            assert_eq!(direction.rotate(-1).rotate(1), direction);
+            let delta: Point<i32> = direction.delta();
+            assert_eq!(direction.reverse().delta(), -delta);
+        }
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::NorthEast.turn_right(), Direction::SouthEast);
+        assert_eq!(Direction::West.rotate(1), Direction::NorthWest);
+        assert_eq!(Direction::SouthWest.reverse(), Direction::NorthEast);
+    }
+
+    #[test]
+    fn test_delta() {
+        let steps: Vec<Point<isize>> = Direction::CARDINALS.iter().map(Direction::delta).collect();
+        assert_eq!(
+            steps,
+            vec![
+                Point { x: 0, y: 1 },
+                Point { x: 1, y: 0 },
+                Point { x: 0, y: -1 },
+                Point { x: -1, y: 0 },
+            ]
+        );
+        for direction in Direction::ALL {
+            let delta: Point<isize> = direction.delta();
+            assert_eq!(delta.chebyshev(&Point { x: 0, y: 0 }), 1, "{direction:?}");
+            assert_eq!(
+                delta.manhattan(&Point { x: 0, y: 0 }),
+                if direction.is_diagonal() { 2 } else { 1 }
+            );
+        }
+    }
+}
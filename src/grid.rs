@@ -0,0 +1,500 @@
+/*
+
+Two-dimensional grids, which come up in puzzle after puzzle.
+
+Grid is the shared behaviour, with two ways of storing the cells:
+
+* SparseGrid keeps them in a HashMap, for grids that start empty and get discovered or painted
+  as we go, possibly off in negative coordinates (day 11's hull, day 13's board, day 15's maze).
+* DenseGrid keeps them in a Vec, row by row, for grids whose size is known up front, such as a
+  picture read from text (day 17's scaffold).
+
+Text and screens have y growing downwards, but some puzzles (day 11) have it growing upwards.  A
+grid knows which way round it is, so that North is always up when printing, drawing or finding
+neighbours.
+
+*/
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use image::{Rgb, RgbImage};
+
+use crate::render::Renderer;
+use crate::{Direction, Point};
+
+/// The smallest rectangle holding every cell, inclusive at both ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub min: Point<isize>,
+    pub max: Point<isize>,
+}
+
+impl Bounds {
+    #[must_use]
+    pub fn new(point: &Point<isize>) -> Self {
+        Bounds {
+            min: *point,
+            max: *point,
+        }
+    }
+
+    /// Grow to include `point`
+    pub fn extend(&mut self, point: &Point<isize>) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+    }
+
+    #[must_use]
+    pub fn contains(&self, point: &Point<isize>) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        (self.max.x - self.min.x + 1) as usize
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        (self.max.y - self.min.y + 1) as usize
+    }
+}
+
+pub trait Grid<T> {
+    fn get(&self, point: &Point<isize>) -> Option<&T>;
+
+    fn set(&mut self, point: Point<isize>, value: T);
+
+    /// None if there's nothing in the grid
+    fn bounds(&self) -> Option<Bounds>;
+
+    /// Every cell that's been set, in no particular order
+    fn iter(&self) -> Box<dyn Iterator<Item = (Point<isize>, &T)> + '_>;
+
+    /// Whether y grows upwards, so North is +y rather than -y
+    fn y_up(&self) -> bool;
+
+    /// The point one step from `point` in `direction`
+    fn step(&self, point: &Point<isize>, direction: &Direction) -> Point<isize> {
+        let mut delta: Point<isize> = direction.delta();
+        if !self.y_up() {
+            delta.y = -delta.y;
+        }
+        *point + delta
+    }
+
+    /// The cells next to `point` that have been set, going clockwise from North
+    fn neighbours(&self, point: &Point<isize>) -> Vec<(Direction, Point<isize>, &T)> {
+        Direction::CARDINALS
+            .into_iter()
+            .filter_map(|direction| {
+                let next = self.step(point, &direction);
+                self.get(&next).map(|value| (direction, next, value))
+            })
+            .collect()
+    }
+
+    /// One line of text per row, North at the top.  `glyph` gets None for cells not set.
+    fn render<F: Fn(Option<&T>) -> char>(&self, glyph: F) -> String {
+        let Some(bounds) = self.bounds() else {
+            return String::new();
+        };
+        let mut text = String::with_capacity((bounds.width() + 1) * bounds.height());
+        for row in 0..bounds.height() as isize {
+            let y = if self.y_up() {
+                bounds.max.y - row
+            } else {
+                bounds.min.y + row
+            };
+            for x in bounds.min.x..=bounds.max.x {
+                text.push(glyph(self.get(&Point { x, y })));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// A picture of the grid, North at the top, with each cell `scale` pixels square.  Cells
+    /// not set are black.  See Renderer for more options.
+    fn to_image<F: Fn(&T) -> Rgb<u8>>(&self, palette: F, scale: u32) -> RgbImage
+    where
+        Self: Sized,
+    {
+        Renderer::new().scale(scale).render(self, &palette)
+    }
+
+    /// Groups of cells joined North, South, East or West to another of the same value
+    fn regions(&self) -> Vec<Vec<Point<isize>>>
+    where
+        T: PartialEq,
+    {
+        let mut seen: HashSet<Point<isize>> = HashSet::new();
+        let mut regions = vec![];
+        for (start, value) in self.iter() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut region = vec![];
+            let mut queue = VecDeque::from([start]);
+            while let Some(point) = queue.pop_front() {
+                for (_, next, other) in self.neighbours(&point) {
+                    if other == value && seen.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+                region.push(point);
+            }
+            regions.push(region);
+        }
+        regions
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point<isize>, T>,
+    bounds: Option<Bounds>,
+    y_up: bool,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid::new()
+    }
+}
+
+impl<T> SparseGrid<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+            bounds: None,
+            y_up: false,
+        }
+    }
+
+    #[must_use]
+    pub fn with_y_up(mut self, y_up: bool) -> Self {
+        self.y_up = y_up;
+        self
+    }
+
+    /// One row per line, with `cell` deciding what each character holds, if anything
+    #[must_use]
+    pub fn parse<F: Fn(char) -> Option<T>>(text: &str, cell: F) -> Self {
+        let mut grid = SparseGrid::new();
+        for (y, line) in text.lines().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                if let Some(value) = cell(c) {
+                    grid.set(
+                        Point {
+                            x: x as isize,
+                            y: y as isize,
+                        },
+                        value,
+                    );
+                }
+            }
+        }
+        grid
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, point: &Point<isize>) -> bool {
+        self.cells.contains_key(point)
+    }
+
+    pub fn get_mut(&mut self, point: &Point<isize>) -> Option<&mut T> {
+        self.cells.get_mut(point)
+    }
+
+    /// The cells themselves, for anything wanting a plain map
+    #[must_use]
+    pub fn cells(&self) -> &HashMap<Point<isize>, T> {
+        &self.cells
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.values()
+    }
+}
+
+impl<T> Grid<T> for SparseGrid<T> {
+    fn get(&self, point: &Point<isize>) -> Option<&T> {
+        self.cells.get(point)
+    }
+
+    fn set(&mut self, point: Point<isize>, value: T) {
+        match &mut self.bounds {
+            Some(bounds) => bounds.extend(&point),
+            None => self.bounds = Some(Bounds::new(&point)),
+        }
+        self.cells.insert(point, value);
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.bounds.clone()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (Point<isize>, &T)> + '_> {
+        Box::new(self.cells.iter().map(|(point, value)| (*point, value)))
+    }
+
+    fn y_up(&self) -> bool {
+        self.y_up
+    }
+}
+
+/// Every cell from 0,0 to `width - 1`,`height - 1` holds a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGrid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    y_up: bool,
+}
+
+impl<T: Clone> DenseGrid<T> {
+    #[must_use]
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        DenseGrid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+            y_up: false,
+        }
+    }
+}
+
+impl<T> DenseGrid<T> {
+    #[must_use]
+    pub fn with_y_up(mut self, y_up: bool) -> Self {
+        self.y_up = y_up;
+        self
+    }
+
+    /// One row per line, with `cell` turning each character into a value
+    ///
+    /// # Panics
+    ///
+    /// If the lines aren't all the same length
+    #[must_use]
+    pub fn parse<F: Fn(char) -> T>(text: &str, cell: F) -> Self {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+        for line in text.lines() {
+            let before = cells.len();
+            cells.extend(line.chars().map(&cell));
+            let length = cells.len() - before;
+            assert_eq!(
+                *width.get_or_insert(length),
+                length,
+                "Line {height} is a different length"
+            );
+            height += 1;
+        }
+        DenseGrid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+            y_up: false,
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_mut(&mut self, point: &Point<isize>) -> Option<&mut T> {
+        self.index(point).map(|index| &mut self.cells[index])
+    }
+
+    fn index(&self, point: &Point<isize>) -> Option<usize> {
+        let x = usize::try_from(point.x).ok().filter(|x| *x < self.width)?;
+        let y = usize::try_from(point.y).ok().filter(|y| *y < self.height)?;
+        Some(y * self.width + x)
+    }
+}
+
+impl<T> Grid<T> for DenseGrid<T> {
+    fn get(&self, point: &Point<isize>) -> Option<&T> {
+        self.index(point).map(|index| &self.cells[index])
+    }
+
+    /// # Panics
+    ///
+    /// If `point` is outside the grid, which can't grow
+    fn set(&mut self, point: Point<isize>, value: T) {
+        let Some(index) = self.index(&point) else {
+            panic!("{point:?} is outside a {}x{} grid", self.width, self.height);
+        };
+        self.cells[index] = value;
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        (!self.cells.is_empty()).then(|| Bounds {
+            min: Point { x: 0, y: 0 },
+            max: Point {
+                x: self.width as isize - 1,
+                y: self.height as isize - 1,
+            },
+        })
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (Point<isize>, &T)> + '_> {
+        Box::new(self.cells.iter().enumerate().map(|(index, value)| {
+            let point = Point {
+                x: (index % self.width) as isize,
+                y: (index / self.width) as isize,
+            };
+            (point, value)
+        }))
+    }
+
+    fn y_up(&self) -> bool {
+        self.y_up
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PICTURE: &str = "\
+#..#
+##.#
+...#";
+
+    fn wall(c: char) -> bool {
+        c == '#'
+    }
+
+    fn glyph(cell: Option<&bool>) -> char {
+        match cell {
+            Some(true) => '#',
+            Some(false) => '.',
+            None => ' ',
+        }
+    }
+
+    #[test]
+    fn test_dense_parse_and_render() {
+        let grid = DenseGrid::parse(PICTURE, wall);
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!(grid.get(&Point { x: 1, y: 1 }), Some(&true));
+        assert_eq!(grid.get(&Point { x: 1, y: 2 }), Some(&false));
+        assert_eq!(grid.get(&Point { x: 4, y: 0 }), None);
+        assert_eq!(grid.get(&Point { x: -1, y: 0 }), None);
+        assert_eq!(grid.render(glyph), format!("{PICTURE}\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "different length")]
+    fn test_dense_ragged() {
+        let _ = DenseGrid::parse("##\n#", wall);
+    }
+
+    #[test]
+    fn test_sparse_bounds() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        grid.set(Point { x: 2, y: -1 }, 'a');
+        grid.set(Point { x: -3, y: 4 }, 'b');
+        let bounds = grid.bounds().unwrap();
+        assert_eq!(bounds.min, Point { x: -3, y: -1 });
+        assert_eq!(bounds.max, Point { x: 2, y: 4 });
+        assert_eq!((bounds.width(), bounds.height()), (6, 6));
+        assert_eq!(grid.len(), 2);
+    }
+
+    #[test]
+    fn test_sparse_parse_and_render() {
+        // Only the walls
+        let grid = SparseGrid::parse(PICTURE, |c| wall(c).then_some(true));
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid.render(glyph), "#  #\n## #\n   #\n");
+    }
+
+    #[test]
+    fn test_y_up() {
+        let mut grid = SparseGrid::new().with_y_up(true);
+        grid.set(Point { x: 0, y: 0 }, true);
+        grid.set(Point { x: 1, y: 1 }, false);
+        assert_eq!(grid.render(glyph), " .\n# \n");
+        assert_eq!(
+            grid.step(&Point { x: 0, y: 0 }, &Direction::North),
+            Point { x: 0, y: 1 }
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = DenseGrid::parse(PICTURE, wall);
+        let neighbours: Vec<(Direction, Point<isize>, bool)> = grid
+            .neighbours(&Point { x: 0, y: 1 })
+            .into_iter()
+            .map(|(direction, point, wall)| (direction, point, *wall))
+            .collect();
+        assert_eq!(
+            neighbours,
+            vec![
+                (Direction::North, Point { x: 0, y: 0 }, true),
+                (Direction::East, Point { x: 1, y: 1 }, true),
+                (Direction::South, Point { x: 0, y: 2 }, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regions() {
+        let grid = DenseGrid::parse(PICTURE, wall);
+        let mut sizes: Vec<(bool, usize)> = grid
+            .regions()
+            .iter()
+            .map(|region| (*grid.get(&region[0]).unwrap(), region.len()))
+            .collect();
+        sizes.sort_unstable();
+        // The floor winds round in one piece, between two separate bits of wall
+        assert_eq!(sizes, vec![(false, 6), (true, 3), (true, 3)]);
+    }
+
+    #[test]
+    fn test_to_image() {
+        let mut grid = SparseGrid::new().with_y_up(true);
+        grid.set(Point { x: 0, y: 0 }, true);
+        grid.set(Point { x: 1, y: 1 }, false);
+        let img = grid.to_image(
+            |wall| {
+                if *wall {
+                    Rgb([255, 255, 255])
+                } else {
+                    Rgb([255, 0, 0])
+                }
+            },
+            2,
+        );
+        assert_eq!(img.dimensions(), (4, 4));
+        assert_eq!(*img.get_pixel(0, 3), Rgb([255, 255, 255]));
+        assert_eq!(*img.get_pixel(3, 0), Rgb([255, 0, 0]));
+        assert_eq!(*img.get_pixel(0, 0), Rgb([0, 0, 0]));
+    }
+}
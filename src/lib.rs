@@ -1,13 +1,35 @@
-use std::fmt::Display;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
-use std::ops::AddAssign;
-
-use num_traits::int::PrimInt;
 
+pub mod animation;
+pub mod answers;
+pub mod ascii;
+pub mod asm;
+#[cfg(feature = "async")]
+pub mod async_vm;
+pub mod cfg;
+pub mod days;
+pub mod decompile;
+pub mod differential;
+pub mod disasm;
+#[cfg(test)]
+mod generate;
+pub mod geometry;
+pub mod grid;
+pub mod memory;
+pub mod ocr;
+pub mod probe;
+pub mod render;
+pub mod search;
+pub mod solution;
+pub mod terminal;
+pub mod thread_vm;
 pub mod vm;
 
+pub use geometry::{Direction, Point, Point3};
+pub use grid::{DenseGrid, Grid, SparseGrid};
+
 // from https://www.reddit.com/r/rust/comments/skmpnr/output_text_to_console_in_debug_mode_only/hvluai2/
 #[macro_export]
 macro_rules! debug_println {
@@ -44,46 +66,3 @@ pub fn get_user_input(query: &str) -> String {
     io::stdin().read_line(&mut input).unwrap();
     input
 }
-
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
-pub struct Point<T: PrimInt + Display> {
-    pub x: T,
-    pub y: T,
-}
-
-impl<T: PrimInt + Display> AddAssign for Point<T> {
-    fn add_assign(&mut self, other: Self) {
-        *self = Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        };
-    }
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub enum Direction {
-    North,
-    South,
-    East,
-    West,
-}
-
-impl Direction {
-    pub fn turn_left(&self) -> Direction {
-        match &self {
-            Direction::North => Direction::West,
-            Direction::South => Direction::East,
-            Direction::East => Direction::North,
-            Direction::West => Direction::South,
-        }
-    }
-
-    pub fn turn_right(&self) -> Direction {
-        match &self {
-            Direction::North => Direction::East,
-            Direction::South => Direction::West,
-            Direction::East => Direction::South,
-            Direction::West => Direction::North,
-        }
-    }
-}
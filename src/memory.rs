@@ -0,0 +1,265 @@
+/*
+
+VM memory.  Normally just a Vec, but it can be switched to fixed size pages that are shared
+between clones until one of them writes.
+
+Cloning a VM copies all of its memory.  Once `share`d, a clone copies the page table instead,
+one pointer per page, bumping reference counts rather than copying any values, and the first
+write to a page copies that page alone.  Growing memory shares a single page of zeros until
+something's written there too.
+
+So a clone is O(pages), not O(1).  Sharing the table itself as well would make clones O(1), but
+then the first write after every clone copies the whole table instead, which is no better.
+
+Sharing is opt in, because for most programs here it doesn't pay.  With day 9's memory (about a
+thousand addresses), cloning shared pages takes nearly twice as long as copying the Vec, and
+running on them is about a sixth slower, since reads go through an extra indirection and every
+write checks (atomically) whether its page is shared.  It's for much bigger memories that are
+forked often and mostly read.  Day 15 opts in anyway, as the one place that forks a VM at every
+step (its maze search), though with a memory that size it's no faster.  See the "clone" group
+and the "shared memory" configuration in benches/vm_benchmark.rs.
+
+Pages are small (64 addresses) as a fork's first write copies a whole one.
+
+Everything past `len` in the last page is kept at zero, so growing within a page needs no work.
+
+*/
+
+use std::fmt;
+use std::ops::Index;
+use std::sync::Arc;
+
+const PAGE_BITS: u32 = 6;
+/// Addresses per page
+pub const PAGE_SIZE: usize = 1 << PAGE_BITS;
+const OFFSET_MASK: usize = PAGE_SIZE - 1;
+
+type Page = [isize; PAGE_SIZE];
+
+#[derive(Clone, Default)]
+pub struct Memory {
+    // All of it, unless shared, in which case this is empty and the pages hold it instead.  So
+    // reads and writes try here first, and the usual unshared case costs no more than a Vec.
+    flat: Vec<isize>,
+    shared: bool,
+    pages: Vec<Arc<Page>>,
+    // How much of the pages is in use
+    paged_len: usize,
+}
+
+impl Memory {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        // Only ever one of these is non-zero
+        self.flat.len() + self.paged_len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Like a slice's `get`: None past the end
+    #[must_use]
+    pub fn get(&self, address: usize) -> Option<&isize> {
+        match self.flat.get(address) {
+            Some(value) => Some(value),
+            None => (address < self.paged_len)
+                .then(|| &self.pages[address >> PAGE_BITS][address & OFFSET_MASK]),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If `address` is past the end.  `grow` first.
+    pub fn set(&mut self, address: usize, value: isize) {
+        if let Some(slot) = self.flat.get_mut(address) {
+            *slot = value;
+            return;
+        }
+        assert!(
+            address < self.paged_len,
+            "Writing {address} past the end of memory ({})",
+            self.len()
+        );
+        Arc::make_mut(&mut self.pages[address >> PAGE_BITS])[address & OFFSET_MASK] = value;
+    }
+
+    /// Make sure there are at least `len` addresses, the new ones all 0
+    pub fn grow(&mut self, len: usize) {
+        if len <= self.len() {
+            return;
+        }
+        if self.shared {
+            let pages = len.div_ceil(PAGE_SIZE);
+            if pages > self.pages.len() {
+                self.pages.resize(pages, Arc::new([0; PAGE_SIZE]));
+            }
+            self.paged_len = len;
+        } else {
+            self.flat.resize(len, 0);
+        }
+    }
+
+    /// Switch to pages shared between clones, from here on.  Does nothing if it already is.
+    pub fn share(&mut self) {
+        if self.shared {
+            return;
+        }
+        self.pages = self
+            .flat
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = [0; PAGE_SIZE];
+                page[..chunk.len()].copy_from_slice(chunk);
+                Arc::new(page)
+            })
+            .collect();
+        self.paged_len = self.flat.len();
+        self.flat = vec![];
+        self.shared = true;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &isize> {
+        self.flat
+            .iter()
+            .chain(self.pages.iter().flat_map(|page| page.iter()))
+            .take(self.len())
+    }
+
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<isize> {
+        self.iter().copied().collect()
+    }
+
+    /// How many pages are still shared with `other`, rather than copies of their own.  None
+    /// unless both are `share`d.
+    #[must_use]
+    pub fn shared_pages(&self, other: &Memory) -> usize {
+        self.pages
+            .iter()
+            .zip(other.pages.iter())
+            .filter(|(mine, theirs)| Arc::ptr_eq(mine, theirs))
+            .count()
+    }
+}
+
+impl From<Vec<isize>> for Memory {
+    fn from(values: Vec<isize>) -> Self {
+        Memory {
+            flat: values,
+            ..Memory::default()
+        }
+    }
+}
+
+impl Index<usize> for Memory {
+    type Output = isize;
+
+    fn index(&self, address: usize) -> &isize {
+        match self.get(address) {
+            Some(value) => value,
+            None => panic!("Reading {address} past the end of memory ({})", self.len()),
+        }
+    }
+}
+
+// Shown as a list, like the Vec it replaced
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Memory {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Memory {}
+
+impl PartialEq<Vec<isize>> for Memory {
+    fn eq(&self, other: &Vec<isize>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let values: Vec<isize> = (0..1000).collect();
+        let memory = Memory::from(values.clone());
+        assert_eq!(memory.len(), 1000);
+        assert_eq!(memory.to_vec(), values);
+        assert_eq!(memory[999], 999);
+        assert_eq!(memory.get(1000), None);
+        assert_eq!(format!("{:?}", Memory::from(vec![1, 2])), "[1, 2]");
+        assert!(Memory::default().is_empty());
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut memory = Memory::from(vec![1, 2, 3]);
+        memory.share();
+        memory.grow(2);
+        assert_eq!(memory.len(), 3);
+        memory.grow(PAGE_SIZE * 3 + 1);
+        assert_eq!(memory.len(), PAGE_SIZE * 3 + 1);
+        assert_eq!(memory[2], 3);
+        assert_eq!(memory[PAGE_SIZE * 3], 0);
+        memory.set(PAGE_SIZE * 2, 7);
+        assert_eq!(memory[PAGE_SIZE * 2], 7);
+        // The other grown pages are still all zeros
+        assert_eq!(memory.iter().filter(|value| **value != 0).count(), 4);
+    }
+
+    #[test]
+    fn test_shared_like_flat() {
+        let values: Vec<isize> = (0..100).collect();
+        let flat = Memory::from(values.clone());
+        let mut shared = flat.clone();
+        shared.share();
+        assert_eq!(shared, flat);
+        assert_eq!(shared.to_vec(), values);
+        assert_eq!(shared.get(99), Some(&99));
+        assert_eq!(shared.get(100), None);
+        assert_eq!(shared.shared_pages(&flat), 0);
+
+        for memory in [flat, shared] {
+            let mut memory = memory;
+            memory.grow(PAGE_SIZE * 3 + 1);
+            memory.set(PAGE_SIZE * 3, 7);
+            assert_eq!(memory.len(), PAGE_SIZE * 3 + 1);
+            assert_eq!(memory[PAGE_SIZE * 3], 7);
+            assert_eq!(memory[PAGE_SIZE * 2], 0);
+        }
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut original = Memory::from(vec![5; PAGE_SIZE * 4]);
+        original.share();
+        let mut fork = original.clone();
+        assert_eq!(fork.shared_pages(&original), 4);
+
+        fork.set(PAGE_SIZE + 1, 6);
+        assert_eq!(fork.shared_pages(&original), 3);
+        assert_eq!(fork[PAGE_SIZE + 1], 6);
+        assert_eq!(original[PAGE_SIZE + 1], 5);
+        assert_ne!(fork, original);
+
+        fork.set(PAGE_SIZE + 1, 5);
+        assert_eq!(fork, original);
+        assert_eq!(fork, vec![5; PAGE_SIZE * 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end of memory")]
+    fn test_set_past_end() {
+        Memory::from(vec![1]).set(1, 2);
+    }
+}
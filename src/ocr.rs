@@ -0,0 +1,96 @@
+/*
+
+Some puzzles (day 11) answer in capital letters drawn as pixels, always in the same blocky font:
+four pixels wide, six tall, with a blank column between letters.  Reading them back into text
+means answers like that can be compared and tested like any other.
+
+Only the letters that have turned up in puzzles are known.
+
+*/
+
+const HEIGHT: usize = 6;
+const WIDTH: usize = 4;
+
+const LETTERS: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Read letters from six rows of text, with `#` for lit pixels and anything else for dark ones.
+/// Blank columns before the first letter are skipped, and anything unrecognised comes out as ?.
+#[must_use]
+pub fn read_letters(picture: &str) -> String {
+    let rows: Vec<Vec<bool>> = picture
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect();
+    if rows.len() != HEIGHT {
+        return String::new();
+    }
+    let lit = |x: usize| rows.iter().any(|row| row.get(x) == Some(&true));
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let Some(mut left) = (0..width).find(|x| lit(*x)) else {
+        return String::new();
+    };
+
+    let mut text = String::new();
+    while (left..width).any(lit) {
+        let glyph: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (left..left + WIDTH)
+                    .map(|x| if row.get(x) == Some(&true) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let letter = LETTERS
+            .iter()
+            .find(|(_, pattern)| pattern.iter().zip(&glyph).all(|(a, b)| a == b))
+            .map_or('?', |(letter, _)| *letter);
+        text.push(letter);
+        left += WIDTH + 1;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_letters() {
+        // Day 11's registration, as the hull renders it
+        let hull = r"
+ #  # ###   ##   ##  #### #     ##  ###
+ #  # #  # #  # #  # #    #    #  # #  #
+ #  # #  # #    #  # ###  #    #    #  #
+ #  # ###  #    #### #    #    #    ###
+ #  # # #  #  # #  # #    #    #  # #
+  ##  #  #  ##  #  # #    ####  ##  #
+";
+        assert_eq!(read_letters(hull), "URCAFLCP");
+    }
+
+    #[test]
+    fn test_unknown() {
+        let picture = "#..#.#...\n.##..#...\n.##..#...\n.##..#...\n.##..#...\n#..#.####\n";
+        assert_eq!(read_letters(picture), "?L");
+        assert_eq!(read_letters("#\n#\n"), "");
+    }
+}
@@ -0,0 +1,71 @@
+/*
+
+Some puzzles (day 19 especially) want a brand new run of the same program for every question
+asked, thousands of times over.  A Probe keeps a pristine copy of the program and resets one VM
+back to it between runs, which reuses the VM's existing allocations rather than building a new
+one from scratch every time.
+
+*/
+
+use std::collections::VecDeque;
+
+use crate::vm::{Snapshot, VM};
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pristine: Snapshot,
+    vm: VM,
+    runs: usize,
+}
+
+impl Probe {
+    #[must_use]
+    pub fn new(program: Vec<isize>) -> Self {
+        let vm = VM::new(program);
+        Probe {
+            pristine: vm.snapshot(),
+            vm,
+            runs: 0,
+        }
+    }
+
+    /// Run a fresh copy of the program with `input`, read in the order given, until it finishes
+    /// or wants more input.  Returns everything it output.
+    pub fn run(&mut self, input: &[isize]) -> &VecDeque<isize> {
+        self.vm.restore(&self.pristine);
+        self.vm.queue_input(input.iter().copied());
+        self.vm.run();
+        self.runs += 1;
+        self.vm.output()
+    }
+
+    /// How many times the program has been run
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_are_independent() {
+        // Adds its input to a counter in its own memory, outputs the counter, and also writes
+        // well past the end of the program
+        let program = vec![3, 20, 1, 20, 21, 21, 4, 21, 1101, 1, 1, 1000, 99];
+        let mut probe = Probe::new(program);
+        assert_eq!(probe.run(&[5]), &VecDeque::from([5]));
+        assert_eq!(probe.run(&[7]), &VecDeque::from([7]));
+        assert_eq!(probe.runs(), 2);
+    }
+
+    #[test]
+    fn test_input_order() {
+        // Output the difference of two inputs
+        let program = vec![3, 20, 3, 21, 1002, 21, -1, 21, 1, 20, 21, 22, 4, 22, 99];
+        let mut probe = Probe::new(program);
+        assert_eq!(probe.run(&[10, 3]), &VecDeque::from([7]));
+    }
+}
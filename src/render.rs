@@ -0,0 +1,458 @@
+/*
+
+Turn a grid into a picture.
+
+Every grid puzzle wants to see its grid, and every one was doing the same dance to get there:
+find the bounds, offset everything to start at 0,0, fill in the background, colour each cell,
+flip it if y grows upwards, then scale it up so it's big enough to see.  A Renderer does all of
+that for any Grid, with a Palette choosing the colours.
+
+Optionally it can draw lines between the cells, which helps when counting them, and a legend
+underneath saying what each colour means.  There's no font to hand, so the legend carries a tiny
+one of its own, which only knows capital letters, digits and a little punctuation.
+
+Pictures come out as PNG, or as PPM for anything that would rather not decode a PNG.
+
+*/
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use image::{ImageBuffer, ImageError, ImageResult, Rgb, RgbImage};
+
+use crate::grid::{Bounds, Grid};
+
+/// Which colour each tile is drawn in, and optionally what they all mean
+pub trait Palette<T> {
+    fn colour(&self, tile: &T) -> Rgb<u8>;
+
+    /// Labels for a legend, in the order they should be listed
+    fn legend(&self) -> Vec<(String, Rgb<u8>)> {
+        vec![]
+    }
+}
+
+/// Any function from tile to colour will do, if a legend isn't needed
+impl<T, F: Fn(&T) -> Rgb<u8>> Palette<T> for F {
+    fn colour(&self, tile: &T) -> Rgb<u8> {
+        self(tile)
+    }
+}
+
+// Each font pixel is drawn this many pixels square
+const FONT_SCALE: u32 = 2;
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+// Space around each legend entry and between its swatch and label
+const PADDING: u32 = 4;
+const SWATCH: u32 = GLYPH_HEIGHT * FONT_SCALE;
+const LEGEND_ROW: u32 = SWATCH + PADDING;
+
+pub struct Renderer {
+    scale: u32,
+    background: Rgb<u8>,
+    grid_lines: Option<Rgb<u8>>,
+    legend: bool,
+    bounds: Option<Bounds>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    /// One pixel per cell on black, with no grid lines or legend
+    #[must_use]
+    pub fn new() -> Self {
+        Renderer {
+            scale: 1,
+            background: Rgb([0, 0, 0]),
+            grid_lines: None,
+            legend: false,
+            bounds: None,
+        }
+    }
+
+    /// Draw each cell as a `scale` x `scale` square
+    #[must_use]
+    pub fn scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Colour for cells not in the grid, and behind the legend
+    #[must_use]
+    pub fn background(mut self, background: Rgb<u8>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Draw a line one pixel wide around every cell
+    #[must_use]
+    pub fn grid_lines(mut self, colour: Rgb<u8>) -> Self {
+        self.grid_lines = Some(colour);
+        self
+    }
+
+    /// List the palette's legend underneath the grid
+    #[must_use]
+    pub fn legend(mut self, legend: bool) -> Self {
+        self.legend = legend;
+        self
+    }
+
+    /// Draw this area, rather than just what the grid covers, leaving out any cells beyond it.
+    /// Frames of a grid that grows can then all come out the same size.
+    #[must_use]
+    pub fn bounds(mut self, bounds: Bounds) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// The grid, North at the top whichever way its y runs.  An empty grid comes out as a single
+    /// empty cell, as there's no such thing as an image with no pixels.
+    #[must_use]
+    pub fn render<T, G: Grid<T>, P: Palette<T>>(&self, grid: &G, palette: &P) -> RgbImage {
+        let bounds = self.bounds.clone().or_else(|| grid.bounds());
+        let (columns, rows) = bounds
+            .as_ref()
+            .map_or((1, 1), |bounds| (bounds.width(), bounds.height()));
+        // With grid lines, each cell is followed by a line, and there's one more at the start
+        let line = u32::from(self.grid_lines.is_some());
+        let pitch = self.scale + line;
+        let grid_width = columns as u32 * pitch + line;
+        let grid_height = rows as u32 * pitch + line;
+
+        let legend = if self.legend {
+            palette.legend()
+        } else {
+            vec![]
+        };
+        let legend_width = legend
+            .iter()
+            .map(|(label, _)| PADDING * 3 + SWATCH + text_width(label))
+            .max()
+            .unwrap_or(0);
+        let legend_height = if legend.is_empty() {
+            0
+        } else {
+            legend.len() as u32 * LEGEND_ROW + PADDING
+        };
+
+        let mut img: RgbImage = ImageBuffer::from_pixel(
+            grid_width.max(legend_width),
+            grid_height + legend_height,
+            self.background,
+        );
+        if let Some(colour) = self.grid_lines {
+            for row in 0..=rows as u32 {
+                fill(&mut img, 0, row * pitch, grid_width, 1, colour);
+            }
+            for column in 0..=columns as u32 {
+                fill(&mut img, column * pitch, 0, 1, grid_height, colour);
+            }
+        }
+        if let Some(bounds) = bounds {
+            for (point, tile) in grid.iter() {
+                if !bounds.contains(&point) {
+                    continue;
+                }
+                let column = (point.x - bounds.min.x) as u32;
+                let row = if grid.y_up() {
+                    (bounds.max.y - point.y) as u32
+                } else {
+                    (point.y - bounds.min.y) as u32
+                };
+                let colour = palette.colour(tile);
+                let (x, y) = (column * pitch + line, row * pitch + line);
+                fill(&mut img, x, y, self.scale, self.scale, colour);
+            }
+        }
+        for (number, (label, colour)) in legend.iter().enumerate() {
+            let y = grid_height + PADDING + number as u32 * LEGEND_ROW;
+            fill(&mut img, PADDING, y, SWATCH, SWATCH, *colour);
+            let x = PADDING * 2 + SWATCH;
+            draw_text(&mut img, x, y, label, contrast(self.background));
+        }
+        img
+    }
+
+    /// Render and save, as PPM if the name ends `.ppm` and PNG otherwise
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be written
+    pub fn save<T, G: Grid<T>, P: Palette<T>, Q: AsRef<Path>>(
+        &self,
+        grid: &G,
+        palette: &P,
+        path: Q,
+    ) -> ImageResult<()> {
+        let img = self.render(grid, palette);
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension == "ppm") {
+            let out = BufWriter::new(File::create(path)?);
+            write_ppm(&img, out).map_err(ImageError::IoError)
+        } else {
+            img.save(path)
+        }
+    }
+}
+
+/// Write `img` as a binary PPM, which is just a short header followed by the raw pixels
+///
+/// # Errors
+///
+/// If writing fails
+pub fn write_ppm<W: Write>(img: &RgbImage, mut out: W) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", img.width(), img.height())?;
+    out.write_all(img.as_raw())?;
+    out.flush()
+}
+
+fn fill(img: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, colour: Rgb<u8>) {
+    for dy in 0..height {
+        for dx in 0..width {
+            if x + dx < img.width() && y + dy < img.height() {
+                img.put_pixel(x + dx, y + dy, colour);
+            }
+        }
+    }
+}
+
+// White on dark backgrounds, black on light ones
+fn contrast(background: Rgb<u8>) -> Rgb<u8> {
+    let Rgb([r, g, b]) = background;
+    if u32::from(r) + u32::from(g) + u32::from(b) > 384 {
+        Rgb([0, 0, 0])
+    } else {
+        Rgb([255, 255, 255])
+    }
+}
+
+fn text_width(text: &str) -> u32 {
+    text.chars().count() as u32 * (GLYPH_WIDTH + 1) * FONT_SCALE
+}
+
+fn draw_text(img: &mut RgbImage, x: u32, y: u32, text: &str, colour: Rgb<u8>) {
+    for (index, c) in text.chars().enumerate() {
+        let left = x + index as u32 * (GLYPH_WIDTH + 1) * FONT_SCALE;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    let top = y + row as u32 * FONT_SCALE;
+                    fill(
+                        img,
+                        left + column * FONT_SCALE,
+                        top,
+                        FONT_SCALE,
+                        FONT_SCALE,
+                        colour,
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Rows of a 3x5 glyph, top first, with the leftmost pixel as the highest bit
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ' ' => [0; 5],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::SparseGrid;
+    use crate::Point;
+
+    const B: Rgb<u8> = Rgb([0, 0, 0]);
+    const W: Rgb<u8> = Rgb([255, 255, 255]);
+    const R: Rgb<u8> = Rgb([255, 0, 0]);
+    const G: Rgb<u8> = Rgb([0, 255, 0]);
+
+    fn colour(wall: &bool) -> Rgb<u8> {
+        if *wall {
+            W
+        } else {
+            R
+        }
+    }
+
+    struct Labelled;
+
+    impl Palette<bool> for Labelled {
+        fn colour(&self, wall: &bool) -> Rgb<u8> {
+            colour(wall)
+        }
+
+        fn legend(&self) -> Vec<(String, Rgb<u8>)> {
+            vec![("Wall".to_owned(), W), ("Floor".to_owned(), R)]
+        }
+    }
+
+    // A wall at 0,0 and floor at 1,1, with 0,1 and 1,0 left empty
+    fn diagonal(y_up: bool) -> SparseGrid<bool> {
+        let mut grid = SparseGrid::new().with_y_up(y_up);
+        grid.set(Point { x: 0, y: 0 }, true);
+        grid.set(Point { x: 1, y: 1 }, false);
+        grid
+    }
+
+    fn pixels(rows: &[&[Rgb<u8>]]) -> Vec<u8> {
+        rows.iter()
+            .flat_map(|row| row.iter().flat_map(|pixel| pixel.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_y_down() {
+        let img = Renderer::new().render(&diagonal(false), &colour);
+        assert_eq!(img.dimensions(), (2, 2));
+        assert_eq!(img.into_raw(), pixels(&[&[W, B], &[B, R]]));
+    }
+
+    #[test]
+    fn test_y_up() {
+        let img = Renderer::new().render(&diagonal(true), &colour);
+        assert_eq!(img.into_raw(), pixels(&[&[B, R], &[W, B]]));
+    }
+
+    #[test]
+    fn test_scale_and_background() {
+        let img = Renderer::new()
+            .scale(2)
+            .background(G)
+            .render(&diagonal(false), &colour);
+        assert_eq!(
+            img.into_raw(),
+            pixels(&[&[W, W, G, G], &[W, W, G, G], &[G, G, R, R], &[G, G, R, R],])
+        );
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        let img = Renderer::new()
+            .grid_lines(G)
+            .render(&diagonal(false), &colour);
+        assert_eq!(
+            img.into_raw(),
+            pixels(&[
+                &[G, G, G, G, G],
+                &[G, W, G, B, G],
+                &[G, G, G, G, G],
+                &[G, B, G, R, G],
+                &[G, G, G, G, G],
+            ])
+        );
+    }
+
+    #[test]
+    fn test_legend() {
+        let plain = Renderer::new().scale(4).render(&diagonal(false), &Labelled);
+        assert_eq!(plain.dimensions(), (8, 8));
+
+        let img = Renderer::new()
+            .scale(4)
+            .legend(true)
+            .render(&diagonal(false), &Labelled);
+        // Wide enough for the longest label, tall enough for both rows
+        assert_eq!(img.width(), PADDING * 3 + SWATCH + text_width("Floor"));
+        assert_eq!(img.height(), 8 + 2 * LEGEND_ROW + PADDING);
+        // The grid's unchanged at the top left
+        assert_eq!(*img.get_pixel(0, 0), W);
+        assert_eq!(*img.get_pixel(7, 7), R);
+        // Swatches in the palette's order
+        assert_eq!(*img.get_pixel(PADDING, 8 + PADDING), W);
+        assert_eq!(*img.get_pixel(PADDING, 8 + PADDING + LEGEND_ROW), R);
+        // The top of the W in "Wall" is lit, the gap in its middle isn't
+        let text = PADDING * 2 + SWATCH;
+        assert_eq!(*img.get_pixel(text, 8 + PADDING), W);
+        assert_eq!(*img.get_pixel(text + FONT_SCALE, 8 + PADDING), B);
+    }
+
+    #[test]
+    fn test_ppm() {
+        let img = Renderer::new().render(&diagonal(false), &colour);
+        let mut out = vec![];
+        write_ppm(&img, &mut out).unwrap();
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend(pixels(&[&[W, B], &[B, R]]));
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_empty() {
+        let grid: SparseGrid<bool> = SparseGrid::new();
+        let img = Renderer::new()
+            .scale(2)
+            .background(G)
+            .render(&grid, &colour);
+        assert_eq!(img.into_raw(), pixels(&[&[G, G], &[G, G]]));
+
+        // Which can be saved, unlike an image with no pixels at all
+        let path = std::env::temp_dir().join(format!("render_empty_{}.png", std::process::id()));
+        Renderer::new().save(&grid, &colour, &path).unwrap();
+        assert_eq!(image::open(&path).unwrap().to_rgb8().dimensions(), (1, 1));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bounds() {
+        // Wider than the grid on the left, and cutting off its bottom row
+        let bounds = Bounds {
+            min: Point { x: -1, y: 0 },
+            max: Point { x: 1, y: 0 },
+        };
+        let img = Renderer::new()
+            .bounds(bounds)
+            .background(G)
+            .render(&diagonal(false), &colour);
+        assert_eq!(img.into_raw(), pixels(&[&[G, W, G]]));
+    }
+}
@@ -0,0 +1,289 @@
+/*
+
+Brute force, across every core.  Some puzzles (day 2 part two, or day 7's phase settings) come
+down to running the same program over and over with different memory patches or inputs until
+one run ends the way we want.  A Search spreads those runs over threads, each cloning the base
+VM, and stops early once there's an answer.  Day 2 is the one using it so far.  (Day 19 runs
+the same program over and over too, but counts every answer in part one, and picks each probe
+from the last in part two, so it has no use for finding a single first match.)
+
+Candidates are handed out in order, and the answer is always the first matching candidate in
+that order, however the threads happen to interleave, so results don't change from run to run.
+Once something matches, only candidates before it are still worth trying.
+
+*/
+
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::vm::VM;
+
+// Candidates taken from the queue at a time, so threads aren't fighting over the lock
+const CHUNK: usize = 64;
+
+/// One run to try: memory to change, then input to queue, in order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Candidate {
+    pub patches: Vec<(usize, isize)>,
+    pub input: Vec<isize>,
+}
+
+/// Every combination of `values` at `addresses`, with the last address changing fastest.  Day 2's
+/// nouns and verbs are `patch_space(&[1, 2], 0..=99)`.
+pub fn patch_space(
+    addresses: &[usize],
+    values: RangeInclusive<isize>,
+) -> impl Iterator<Item = Candidate> + Send {
+    let addresses = addresses.to_vec();
+    let values: Vec<isize> = values.collect();
+    let count = u32::try_from(addresses.len())
+        .ok()
+        .and_then(|exponent| values.len().checked_pow(exponent))
+        .expect("Far too many combinations to try");
+    (0..count).map(move |mut index| {
+        let mut patches = vec![(0, 0); addresses.len()];
+        for (patch, address) in patches.iter_mut().zip(&addresses).rev() {
+            *patch = (*address, values[index % values.len()]);
+            index /= values.len();
+        }
+        Candidate {
+            patches,
+            input: vec![],
+        }
+    })
+}
+
+/// A candidate per input vector
+pub fn input_space<I>(inputs: I) -> impl Iterator<Item = Candidate> + Send
+where
+    I: IntoIterator<Item = Vec<isize>>,
+    I::IntoIter: Send,
+{
+    inputs.into_iter().map(|input| Candidate {
+        patches: vec![],
+        input,
+    })
+}
+
+/// The candidate that matched, and the VM as it was when the predicate accepted it
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub index: usize,
+    pub candidate: Candidate,
+    pub vm: VM,
+}
+
+/// How a search went
+#[derive(Debug, Clone)]
+pub struct SearchReport {
+    pub found: Option<Found>,
+    /// Candidates actually run
+    pub tried: usize,
+    /// Of those, how many went wrong or ran out of steps
+    pub failed: usize,
+    pub threads: usize,
+    pub elapsed: Duration,
+}
+
+impl SearchReport {
+    /// Candidates tried per second
+    #[must_use]
+    pub fn throughput(&self) -> f64 {
+        self.tried as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.found {
+            Some(found) => write!(f, "Found candidate {}", found.index)?,
+            None => write!(f, "Found nothing")?,
+        }
+        write!(
+            f,
+            " after {} runs ({} failed) in {:.2?} on {} threads, {:.0} runs/s",
+            self.tried,
+            self.failed,
+            self.elapsed,
+            self.threads,
+            self.throughput()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Search {
+    base: VM,
+    threads: usize,
+    max_steps: Option<usize>,
+}
+
+impl Search {
+    /// Search from `base`, which is cloned for every candidate, on as many threads as there are
+    /// cores
+    #[must_use]
+    pub fn new(base: VM) -> Self {
+        Search {
+            base,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            max_steps: None,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If `threads` is 0
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Can't search on no threads");
+        self.threads = threads;
+        self
+    }
+
+    /// Give up on candidates that run for more than `max_steps` instructions, counting them as
+    /// failed
+    #[must_use]
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    // Run one candidate, returning the VM once it's stopped by itself, or None if it failed
+    fn attempt(&self, candidate: &Candidate) -> Option<VM> {
+        let mut vm = self.base.clone();
+        for (address, value) in &candidate.patches {
+            vm.set_memory(*address, *value);
+        }
+        vm.queue_input(candidate.input.iter().copied());
+        let stopped = match self.max_steps {
+            Some(max_steps) => vm.try_run_for(max_steps),
+            None => vm.try_run().map(|()| true),
+        };
+        matches!(stopped, Ok(true)).then_some(vm)
+    }
+
+    /// Try candidates from `space` until `predicate` accepts a VM's final state, or there are
+    /// none left
+    pub fn run<I, P>(&self, space: I, predicate: P) -> SearchReport
+    where
+        I: IntoIterator<Item = Candidate>,
+        I::IntoIter: Send,
+        P: Fn(&VM) -> bool + Sync,
+    {
+        let start = Instant::now();
+        let queue = Mutex::new(space.into_iter().enumerate());
+        // Index of the earliest match so far
+        let best = AtomicUsize::new(usize::MAX);
+        let found: Mutex<Option<Found>> = Mutex::new(None);
+        let tried = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| loop {
+                    let chunk: Vec<(usize, Candidate)> =
+                        queue.lock().unwrap().by_ref().take(CHUNK).collect();
+                    if chunk.is_empty() {
+                        return;
+                    }
+                    for (index, candidate) in chunk {
+                        if index > best.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        tried.fetch_add(1, Ordering::Relaxed);
+                        let Some(vm) = self.attempt(&candidate) else {
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            continue;
+                        };
+                        if predicate(&vm) {
+                            best.fetch_min(index, Ordering::Relaxed);
+                            let mut found = found.lock().unwrap();
+                            if found.as_ref().is_none_or(|found| index < found.index) {
+                                *found = Some(Found {
+                                    index,
+                                    candidate,
+                                    vm,
+                                });
+                            }
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+
+        SearchReport {
+            found: found.into_inner().unwrap(),
+            tried: tried.into_inner(),
+            failed: failed.into_inner(),
+            threads: self.threads,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Adds the values at 1 and 2, leaving the sum at 0
+    fn adder() -> VM {
+        VM::new(vec![1101, 0, 0, 0, 99])
+    }
+
+    #[test]
+    fn test_patch_space() {
+        let space: Vec<Candidate> = patch_space(&[1, 2], 0..=2).collect();
+        assert_eq!(space.len(), 9);
+        assert_eq!(space[0].patches, vec![(1, 0), (2, 0)]);
+        assert_eq!(space[1].patches, vec![(1, 0), (2, 1)]);
+        assert_eq!(space[8].patches, vec![(1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        // Plenty of pairs add up to 150, but (51, 99) comes first
+        for threads in [1, 4, 16] {
+            let report = Search::new(adder())
+                .threads(threads)
+                .run(patch_space(&[1, 2], 0..=99), |vm| vm.memory()[0] == 150);
+            let found = report.found.unwrap();
+            assert_eq!(found.candidate.patches, vec![(1, 51), (2, 99)]);
+            assert_eq!(found.index, 5199);
+            assert_eq!(found.vm.memory()[0], 150);
+            assert!(report.tried >= 5200);
+        }
+    }
+
+    #[test]
+    fn test_nothing_found() {
+        let report = Search::new(adder())
+            .threads(3)
+            .run(patch_space(&[1, 2], 0..=9), |vm| vm.memory()[0] == 100);
+        assert!(report.found.is_none());
+        assert_eq!(report.tried, 100);
+        assert_eq!(report.failed, 0);
+        assert!(report
+            .to_string()
+            .starts_with("Found nothing after 100 runs"));
+    }
+
+    #[test]
+    fn test_inputs_and_failures() {
+        // Outputs input * 3, except for 0, which jumps into the middle of an instruction
+        let program = vec![3, 20, 1006, 20, 1, 1002, 20, 3, 21, 4, 21, 99];
+        let space = input_space((0..5).map(|value| vec![value]));
+        let report = Search::new(VM::new(program))
+            .threads(2)
+            .max_steps(100)
+            .run(space, |vm| vm.output().back() == Some(&12));
+        let found = report.found.unwrap();
+        assert_eq!(found.candidate.input, vec![4]);
+        assert_eq!(report.failed, 1);
+    }
+}
@@ -0,0 +1,173 @@
+/*
+
+Every day's puzzle has the same shape: read some input, parse it, answer part one, answer part
+two.  A Solution captures that shape, which lets one runner (the aoc binary) run any day, or all
+of them, and lets each day's own binary shrink down to a call to `run_main`.
+
+Solutions each parse into their own type, so they can't sit in a list together as they are.
+Runner hides the input type, and every Solution is a Runner.
+
+*/
+
+use std::fmt::Display;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use log::info;
+use simple_logger::SimpleLogger;
+
+pub trait Solution {
+    /// Whatever the input parses into, usually an Intcode program
+    type Input;
+
+    /// Part one's answer, which is usually a number
+    type One: Display;
+
+    /// Part two's answer
+    type Two: Display;
+
+    /// Which day of the calendar this is
+    fn day(&self) -> u32;
+
+    fn parse(&self, input: &str) -> Self::Input;
+
+    fn part_one(&self, input: &Self::Input) -> Self::One;
+
+    fn part_two(&self, input: &Self::Input) -> Self::Two;
+}
+
+/// Answers, written out, and how long everything took
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub day: u32,
+    pub parse: Duration,
+    pub part_one: String,
+    pub part_one_time: Duration,
+    pub part_two: String,
+    pub part_two_time: Duration,
+}
+
+impl Report {
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.parse + self.part_one_time + self.part_two_time
+    }
+}
+
+/// A Solution with its input type hidden
+pub trait Runner {
+    fn day(&self) -> u32;
+
+    /// Parse `input` and answer both parts
+    fn run(&self, input: &str) -> Report;
+}
+
+impl<S: Solution> Runner for S {
+    fn day(&self) -> u32 {
+        Solution::day(self)
+    }
+
+    fn run(&self, input: &str) -> Report {
+        let start = Instant::now();
+        let parsed = self.parse(input);
+        let parse = start.elapsed();
+
+        let start = Instant::now();
+        let part_one = self.part_one(&parsed).to_string();
+        let part_one_time = start.elapsed();
+
+        let start = Instant::now();
+        let part_two = self.part_two(&parsed).to_string();
+        let part_two_time = start.elapsed();
+
+        Report {
+            day: Solution::day(self),
+            parse,
+            part_one,
+            part_one_time,
+            part_two,
+            part_two_time,
+        }
+    }
+}
+
+/// Where a day's input lives, relative to the top of the repository
+#[must_use]
+pub fn input_path(day: u32) -> String {
+    format!("./input/day{day}")
+}
+
+/// Read and parse a day's usual input
+///
+/// # Panics
+///
+/// If the input isn't there
+pub fn parse_input<S: Solution>(solution: &S) -> S::Input {
+    let path = input_path(solution.day());
+    let input =
+        fs::read_to_string(&path).unwrap_or_else(|error| panic!("Can't read {path}: {error}"));
+    solution.parse(&input)
+}
+
+/// What every day's binary does: read that day's input, answer both parts, and log the answers
+/// along with how long it all took
+///
+/// # Panics
+///
+/// If the input can't be read, or the logger can't be set up
+pub fn run_main<R: Runner + ?Sized>(solution: &R) {
+    let start = Instant::now();
+    SimpleLogger::new().env().init().unwrap();
+    info!("Reading input");
+    let input = fs::read_to_string(input_path(solution.day())).expect("Unable to read file");
+    let report = solution.run(&input);
+    info!("Parsing input took: {:?}", report.parse);
+    info!("Part one: {}", report.part_one);
+    info!("Part one took: {:?}", report.part_one_time);
+    info!("Part two: {}", report.part_two);
+    info!("Part two took: {:?}", report.part_two_time);
+    info!("Overall time take: {:?}", start.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sums and multiplies a list of numbers
+    struct Arithmetic;
+
+    impl Solution for Arithmetic {
+        type Input = Vec<u32>;
+        type One = u32;
+        type Two = u32;
+
+        fn day(&self) -> u32 {
+            99
+        }
+
+        fn parse(&self, input: &str) -> Vec<u32> {
+            input
+                .split_whitespace()
+                .map(|x| x.parse().unwrap())
+                .collect()
+        }
+
+        fn part_one(&self, input: &Vec<u32>) -> u32 {
+            input.iter().sum()
+        }
+
+        fn part_two(&self, input: &Vec<u32>) -> u32 {
+            input.iter().product()
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let runners: Vec<Box<dyn Runner>> = vec![Box::new(Arithmetic)];
+        let report = runners[0].run("2 3 4");
+        assert_eq!(report.day, 99);
+        assert_eq!(report.part_one, "9");
+        assert_eq!(report.part_two, "24");
+        assert!(report.total() >= report.part_one_time);
+    }
+}
@@ -0,0 +1,354 @@
+/*
+
+Draw a grid simulation in the terminal as it runs, using ANSI escape codes.
+
+The first frame clears the screen and draws everything.  After that only the cells that changed
+since the last frame are redrawn, each by moving the cursor straight to it, so even a big board
+updating thousands of times stays smooth.  If the grid grows past what's been drawn (day 11's
+robot wandering off the edge, say) the whole thing is drawn again at the new size.
+
+*/
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::grid::Bounds;
+use crate::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Colour {
+    // Select Graphic Rendition code for the foreground
+    fn code(self) -> u8 {
+        match self {
+            Colour::Default => 39,
+            Colour::Black => 30,
+            Colour::Red => 31,
+            Colour::Green => 32,
+            Colour::Yellow => 33,
+            Colour::Blue => 34,
+            Colour::Magenta => 35,
+            Colour::Cyan => 36,
+            Colour::White => 37,
+        }
+    }
+}
+
+/// How to draw a single tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub glyph: char,
+    pub colour: Colour,
+}
+
+impl Style {
+    #[must_use]
+    pub fn new(glyph: char, colour: Colour) -> Self {
+        Style { glyph, colour }
+    }
+}
+
+const BLANK: Style = Style {
+    glyph: ' ',
+    colour: Colour::Default,
+};
+
+/// Draws grids of `T`, styled by `style`, to `out` (stdout unless told otherwise)
+pub struct Screen<T, S: Fn(&T) -> Style, W: Write = io::Stdout> {
+    style: S,
+    out: W,
+    y_up: bool,
+    frame_time: Option<Duration>,
+    last_frame: Option<Instant>,
+    drawn: HashMap<Point<isize>, Style>,
+    bounds: Option<Bounds>,
+    caption: String,
+    drawn_caption: Option<String>,
+    _tile: std::marker::PhantomData<T>,
+}
+
+impl<T, S: Fn(&T) -> Style> Screen<T, S> {
+    #[must_use]
+    pub fn new(style: S) -> Self {
+        Screen::with_writer(style, io::stdout())
+    }
+}
+
+impl<T, S: Fn(&T) -> Style, W: Write> Screen<T, S, W> {
+    #[must_use]
+    pub fn with_writer(style: S, out: W) -> Self {
+        Screen {
+            style,
+            out,
+            y_up: false,
+            frame_time: None,
+            last_frame: None,
+            drawn: HashMap::new(),
+            bounds: None,
+            caption: String::new(),
+            drawn_caption: None,
+            _tile: std::marker::PhantomData,
+        }
+    }
+
+    /// Whether y grows upwards (as on day 11's hull) rather than down the screen
+    #[must_use]
+    pub fn y_up(mut self, y_up: bool) -> Self {
+        self.y_up = y_up;
+        self
+    }
+
+    /// Draw at most `fps` frames a second, waiting before a frame that comes too soon
+    #[must_use]
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.frame_time = (fps > 0).then(|| Duration::from_secs(1) / fps);
+        self
+    }
+
+    /// A line of text to show under the grid, such as the score
+    pub fn set_caption(&mut self, caption: &str) {
+        caption.clone_into(&mut self.caption);
+    }
+
+    /// Bring the terminal up to date with `cells`.  Anything drawn last time and missing now is
+    /// blanked out.
+    ///
+    /// # Errors
+    ///
+    /// If writing to the terminal fails
+    pub fn draw<'a, I>(&mut self, cells: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = (&'a Point<isize>, &'a T)>,
+        T: 'a,
+    {
+        let frame: HashMap<Point<isize>, Style> = cells
+            .into_iter()
+            .map(|(point, tile)| (*point, (self.style)(tile)))
+            .collect();
+
+        let mut text = String::new();
+        let grown = match &self.bounds {
+            None => true,
+            Some(bounds) => !frame.keys().all(|point| bounds.contains(point)),
+        };
+        if grown {
+            self.bounds = Some(Self::bounds_of(&frame, self.bounds.take()));
+            self.drawn.clear();
+            self.drawn_caption = None;
+            // Clear the screen and hide the cursor
+            text.push_str("\x1b[2J\x1b[?25l");
+        }
+
+        let stale: Vec<Point<isize>> = self
+            .drawn
+            .keys()
+            .filter(|point| !frame.contains_key(point))
+            .cloned()
+            .collect();
+        for point in stale {
+            self.draw_cell(&mut text, &point, BLANK);
+            self.drawn.remove(&point);
+        }
+        for (point, style) in frame {
+            if self.drawn.get(&point) != Some(&style) {
+                self.draw_cell(&mut text, &point, style);
+                self.drawn.insert(point, style);
+            }
+        }
+        if self.drawn_caption.as_ref() != Some(&self.caption) {
+            let row = self.rows() + 1;
+            // Move under the grid and clear the line first, in case the new caption is shorter
+            let _ = write!(text, "\x1b[{row};1H\x1b[0m\x1b[2K{}", self.caption);
+            self.drawn_caption = Some(self.caption.clone());
+        }
+
+        self.throttle();
+        if !text.is_empty() {
+            self.out.write_all(text.as_bytes())?;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Draw for grids kept in a HashMap, which is most of them
+    ///
+    /// # Errors
+    ///
+    /// If writing to the terminal fails
+    pub fn draw_map(&mut self, map: &HashMap<Point<isize>, T>) -> io::Result<()> {
+        self.draw(map.iter())
+    }
+
+    /// Put the colours and cursor back, and leave the cursor under everything drawn
+    ///
+    /// # Errors
+    ///
+    /// If writing to the terminal fails
+    pub fn finish(&mut self) -> io::Result<()> {
+        let row = self.rows() + 2;
+        write!(self.out, "\x1b[{row};1H\x1b[0m\x1b[?25h")?;
+        self.out.flush()
+    }
+
+    /// Whatever's been written so far, mostly of interest to tests
+    #[must_use]
+    pub fn writer(&self) -> &W {
+        &self.out
+    }
+
+    fn rows(&self) -> isize {
+        self.bounds
+            .as_ref()
+            .map_or(0, |bounds| bounds.max.y - bounds.min.y + 1)
+    }
+
+    // The grid only ever gets bigger, so cells drawn before still fit
+    fn bounds_of(frame: &HashMap<Point<isize>, Style>, previous: Option<Bounds>) -> Bounds {
+        let mut points = frame.keys();
+        let mut bounds =
+            previous.unwrap_or_else(|| Bounds::new(points.next().unwrap_or(&Point { x: 0, y: 0 })));
+        for point in points {
+            bounds.extend(point);
+        }
+        bounds
+    }
+
+    fn draw_cell(&self, text: &mut String, point: &Point<isize>, style: Style) {
+        let Some(bounds) = &self.bounds else {
+            return;
+        };
+        // Terminal rows and columns count from 1
+        let column = point.x - bounds.min.x + 1;
+        let row = if self.y_up {
+            bounds.max.y - point.y + 1
+        } else {
+            point.y - bounds.min.y + 1
+        };
+        let _ = write!(
+            text,
+            "\x1b[{row};{column}H\x1b[{}m{}",
+            style.colour.code(),
+            style.glyph
+        );
+    }
+
+    fn throttle(&mut self) {
+        if let (Some(frame_time), Some(last_frame)) = (self.frame_time, self.last_frame) {
+            let elapsed = last_frame.elapsed();
+            if elapsed < frame_time {
+                thread::sleep(frame_time - elapsed);
+            }
+        }
+        self.last_frame = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(tile: &u8) -> Style {
+        match tile {
+            0 => Style::new('.', Colour::Default),
+            _ => Style::new('#', Colour::Red),
+        }
+    }
+
+    fn screen() -> Screen<u8, fn(&u8) -> Style, Vec<u8>> {
+        Screen::with_writer(style, vec![])
+    }
+
+    fn written(screen: &Screen<u8, fn(&u8) -> Style, Vec<u8>>) -> String {
+        String::from_utf8(screen.writer().clone()).unwrap()
+    }
+
+    fn grid(cells: &[(isize, isize, u8)]) -> HashMap<Point<isize>, u8> {
+        cells
+            .iter()
+            .map(|(x, y, tile)| (Point { x: *x, y: *y }, *tile))
+            .collect()
+    }
+
+    #[test]
+    fn test_first_frame_draws_everything() {
+        let mut screen = screen();
+        screen
+            .draw_map(&grid(&[(0, 0, 0), (1, 0, 1), (1, 1, 0)]))
+            .unwrap();
+        let text = written(&screen);
+        assert!(text.starts_with("\x1b[2J"));
+        assert!(text.contains("\x1b[1;1H\x1b[39m."));
+        assert!(text.contains("\x1b[1;2H\x1b[31m#"));
+        assert!(text.contains("\x1b[2;2H\x1b[39m."));
+    }
+
+    #[test]
+    fn test_only_changes_are_redrawn() {
+        let mut screen = screen();
+        let mut map = grid(&[(0, 0, 0), (1, 0, 1), (1, 1, 0)]);
+        screen.draw_map(&map).unwrap();
+        let before = written(&screen).len();
+
+        // Nothing changed, so nothing to write
+        screen.draw_map(&map).unwrap();
+        assert_eq!(written(&screen).len(), before);
+
+        map.insert(Point { x: 0, y: 0 }, 1);
+        map.remove(&Point { x: 1, y: 1 });
+        screen.draw_map(&map).unwrap();
+        let text = written(&screen)[before..].to_owned();
+        assert_eq!(text.matches('H').count(), 2);
+        assert!(text.contains("\x1b[1;1H\x1b[31m#"));
+        assert!(text.contains("\x1b[2;2H\x1b[39m "));
+    }
+
+    #[test]
+    fn test_growing_redraws() {
+        let mut screen = screen().y_up(true);
+        screen.draw_map(&grid(&[(0, 0, 1)])).unwrap();
+        let before = written(&screen).len();
+        screen.draw_map(&grid(&[(0, 0, 1), (0, 1, 0)])).unwrap();
+        let text = written(&screen)[before..].to_owned();
+        assert!(text.starts_with("\x1b[2J"));
+        // y = 1 is now the top row, pushing y = 0 down one
+        assert!(text.contains("\x1b[1;1H\x1b[39m."));
+        assert!(text.contains("\x1b[2;1H\x1b[31m#"));
+    }
+
+    #[test]
+    fn test_caption() {
+        let mut screen = screen();
+        screen.set_caption("Score: 1");
+        screen.draw_map(&grid(&[(0, 0, 0), (0, 1, 0)])).unwrap();
+        assert!(written(&screen).ends_with("\x1b[3;1H\x1b[0m\x1b[2KScore: 1"));
+        let before = written(&screen).len();
+        screen.draw_map(&grid(&[(0, 0, 0), (0, 1, 0)])).unwrap();
+        assert_eq!(written(&screen).len(), before);
+    }
+
+    #[test]
+    fn test_fps() {
+        let mut screen = screen().fps(50);
+        let map = grid(&[(0, 0, 0)]);
+        let start = Instant::now();
+        for _ in 0..4 {
+            screen.draw_map(&map).unwrap();
+        }
+        // The first frame goes straight away, then 20ms between each
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
+}
@@ -0,0 +1,217 @@
+/*
+
+A VM on its own thread, talking over channels.  Simpler than `async_vm` and needs nothing
+beyond std, at the cost of a whole thread each, which is no cost at all for a handful of
+VMs like day 7's amplifiers.
+
+`VmHandle::spawn` starts one and keeps both ends of its channels: `send` input, `recv` output,
+and `join` for the VM as it ended.  To plug VMs straight into each other instead, with no one
+relaying, spawn a thread running `run` on whatever channels you like.
+
+*/
+
+use std::panic;
+use std::sync::mpsc::{channel, Receiver, SendError, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::vm::{DriveError, VM};
+
+// Instructions run between sending output, so it isn't all held back until the VM stops
+const SLICE: usize = 10_000;
+
+/// Run `vm` until it halts, blocking on `input` whenever it needs more and sending its output to
+/// `output` as it goes.  Anything already queued on the VM is read before `input`.  Returns the
+/// VM as it finished.
+///
+/// # Errors
+///
+/// If the program goes wrong, or either channel closes while it's still needed.
+pub fn run(mut vm: VM, input: &Receiver<isize>, output: &Sender<isize>) -> Result<VM, DriveError> {
+    loop {
+        // Whatever it output before going wrong still gets sent
+        let ran = vm.try_run_for(SLICE);
+        while let Some(value) = vm.pop_front_output() {
+            output
+                .send(value)
+                .map_err(|_| DriveError::OutputClosed { value })?;
+        }
+        let stopped = ran?;
+        if vm.needs_input() {
+            let value = input.recv().map_err(|_| DriveError::InputClosed {
+                pointer: vm.pointer(),
+            })?;
+            vm.push_input(value);
+        } else if stopped {
+            return Ok(vm);
+        }
+    }
+}
+
+/// A VM running on its own thread
+#[derive(Debug)]
+pub struct VmHandle {
+    input: Sender<isize>,
+    output: Receiver<isize>,
+    thread: JoinHandle<Result<VM, DriveError>>,
+}
+
+impl VmHandle {
+    #[must_use]
+    pub fn spawn(vm: VM) -> Self {
+        let (input, from_handle) = channel();
+        let (to_handle, output) = channel();
+        let thread = thread::spawn(move || run(vm, &from_handle, &to_handle));
+        VmHandle {
+            input,
+            output,
+            thread,
+        }
+    }
+
+    /// Give the VM another input, to be read after anything sent before
+    ///
+    /// # Errors
+    ///
+    /// If the VM has already stopped, so will never read it, handing the value back
+    pub fn send(&self, value: isize) -> Result<(), SendError<isize>> {
+        self.input.send(value)
+    }
+
+    /// Wait for the VM's next output.  None once it has stopped and everything it output has
+    /// been received.
+    #[must_use]
+    pub fn recv(&self) -> Option<isize> {
+        self.output.recv().ok()
+    }
+
+    /// Every output from here until the VM stops
+    pub fn outputs(&self) -> impl Iterator<Item = isize> + '_ {
+        self.output.iter()
+    }
+
+    /// Wait for the VM to stop.  It won't be sent anything more, so if it's still waiting for
+    /// input that's an error.  Output nobody received is left on the returned VM.
+    ///
+    /// # Errors
+    ///
+    /// Whatever stopped the VM, as `run`
+    ///
+    /// # Panics
+    ///
+    /// If the VM's thread did, passing the panic on
+    pub fn join(self) -> Result<VM, DriveError> {
+        let VmHandle {
+            input,
+            output,
+            thread,
+        } = self;
+        drop(input);
+        let result = thread
+            .join()
+            .unwrap_or_else(|panic| panic::resume_unwind(panic));
+        result.map(|mut vm| {
+            for value in output.try_iter() {
+                vm.push_output(value);
+            }
+            vm
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Day 7's second feedback loop example: the phase, then signals in and out until it halts
+    const AMPLIFIER: &[isize] = &[
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    #[test]
+    fn test_echo() {
+        // Outputs whatever it reads, forever
+        let echo = VmHandle::spawn(VM::new(vec![3, 7, 4, 7, 1105, 1, 0, 0]));
+        for value in [5, -3, 7] {
+            echo.send(value).unwrap();
+            assert_eq!(echo.recv(), Some(value));
+        }
+        assert_eq!(
+            echo.join().unwrap_err(),
+            DriveError::InputClosed { pointer: 0 }
+        );
+    }
+
+    #[test]
+    fn test_amplifier_loop() {
+        // Each signal goes round the amplifiers in turn, until the first of them halts
+        let amplifiers: Vec<VmHandle> = [9, 8, 7, 6, 5]
+            .into_iter()
+            .map(|phase| {
+                let mut amplifier = VM::new(AMPLIFIER.to_vec());
+                amplifier.push_input(phase);
+                VmHandle::spawn(amplifier)
+            })
+            .collect();
+        let mut signal = 0;
+        'feedback: loop {
+            for amplifier in &amplifiers {
+                let next = amplifier.send(signal).ok().and_then(|()| amplifier.recv());
+                match next {
+                    Some(next) => signal = next,
+                    None => break 'feedback,
+                }
+            }
+        }
+        assert_eq!(signal, 139_629_729);
+        for amplifier in amplifiers {
+            assert!(amplifier.join().unwrap().finished());
+        }
+    }
+
+    #[test]
+    fn test_wired_together() {
+        // Each one adds one to what it's given, chained straight into the next with no relaying
+        let increment = vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+        let (first, mut input) = channel();
+        let mut threads = vec![];
+        for _ in 0..10 {
+            let (sender, receiver) = channel();
+            let vm = VM::new(increment.clone());
+            threads.push(thread::spawn(move || run(vm, &input, &sender)));
+            input = receiver;
+        }
+        first.send(100).unwrap();
+        assert_eq!(input.recv(), Ok(110));
+        for thread in threads {
+            assert!(thread.join().unwrap().unwrap().finished());
+        }
+    }
+
+    #[test]
+    fn test_final_state() {
+        // Outputs 1, 2, 3 without being asked, then halts, with 3 left at address 11
+        let program = vec![104, 1, 104, 2, 1101, 1, 2, 11, 4, 11, 99, 0];
+        let handle = VmHandle::spawn(VM::new(program));
+        assert_eq!(handle.recv(), Some(1));
+        let mut vm = handle.join().unwrap();
+        assert_eq!(vm.memory()[11], 3);
+        assert_eq!(vm.pop_front_output(), Some(2));
+        assert_eq!(vm.pop_front_output(), Some(3));
+
+        let handle = VmHandle::spawn(VM::new(vec![104, 1, 104, 2, 99]));
+        assert_eq!(handle.outputs().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(handle.send(3), Err(SendError(3)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let handle = VmHandle::spawn(VM::new(vec![104, 1, 42]));
+        assert_eq!(handle.recv(), Some(1));
+        assert_eq!(handle.recv(), None);
+        assert_eq!(
+            handle.join().unwrap_err().to_string(),
+            "Invalid opcode 42 at 2".to_string()
+        );
+    }
+}
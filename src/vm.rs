@@ -12,8 +12,9 @@ I imagine in the real world, that could be a major problem.  For AoC I imagine i
 use std::collections::VecDeque;
 use std::fmt::Display;
 
-use crate::debug_println;
+use crate::memory::Memory;
 
+use log::trace;
 use num_traits::int::PrimInt;
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -25,7 +26,7 @@ enum VMState {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
-enum OC {
+pub enum OC {
     Add,
     Mul,
     Input,
@@ -38,37 +39,216 @@ enum OC {
     End,
 }
 
-fn decode_opcode<T: PrimInt + Display>(input: T) -> OC {
-    let last_two = input.to_usize().unwrap() % 100;
+impl OC {
+    /// How many parameters follow the opcode in memory
+    #[must_use]
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            OC::Add | OC::Mul | OC::LessThan | OC::Equals => 3,
+            OC::JumpIfTrue | OC::JumpIfFalse => 2,
+            OC::Input | OC::Output | OC::RelativeBaseOffset => 1,
+            OC::End => 0,
+        }
+    }
+
+    /// The numeric opcode, without any parameter modes
+    #[must_use]
+    pub fn code(&self) -> isize {
+        match self {
+            OC::Add => 1,
+            OC::Mul => 2,
+            OC::Input => 3,
+            OC::Output => 4,
+            OC::JumpIfTrue => 5,
+            OC::JumpIfFalse => 6,
+            OC::LessThan => 7,
+            OC::Equals => 8,
+            OC::RelativeBaseOffset => 9,
+            OC::End => 99,
+        }
+    }
+
+    /// Short name used when printing disassembly
+    #[must_use]
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            OC::Add => "ADD",
+            OC::Mul => "MUL",
+            OC::Input => "IN",
+            OC::Output => "OUT",
+            OC::JumpIfTrue => "JNZ",
+            OC::JumpIfFalse => "JZ",
+            OC::LessThan => "LT",
+            OC::Equals => "EQ",
+            OC::RelativeBaseOffset => "ARB",
+            OC::End => "HALT",
+        }
+    }
+}
+
+/// The opcode in the last two digits of a memory cell, ignoring the parameter modes above them.
+/// None if the cell is negative or those digits aren't an opcode, as when it's really data.
+pub fn try_decode_opcode<T: PrimInt + Display>(input: T) -> Option<OC> {
+    let last_two = input.to_usize()? % 100;
     match last_two {
-        1 => OC::Add,
-        2 => OC::Mul,
-        3 => OC::Input,
-        4 => OC::Output,
-        5 => OC::JumpIfTrue,
-        6 => OC::JumpIfFalse,
-        7 => OC::LessThan,
-        8 => OC::Equals,
-        9 => OC::RelativeBaseOffset,
-        99 => OC::End,
-        _ => panic!("Invalid opcode: {}", last_two),
+        1 => Some(OC::Add),
+        2 => Some(OC::Mul),
+        3 => Some(OC::Input),
+        4 => Some(OC::Output),
+        5 => Some(OC::JumpIfTrue),
+        6 => Some(OC::JumpIfFalse),
+        7 => Some(OC::LessThan),
+        8 => Some(OC::Equals),
+        9 => Some(OC::RelativeBaseOffset),
+        99 => Some(OC::End),
+        _ => None,
+    }
+}
+
+/// Read a program as the puzzles give it: one line of comma separated numbers
+///
+/// # Panics
+///
+/// If anything in it isn't a number
+#[must_use]
+pub fn parse_program(text: &str) -> Vec<isize> {
+    text.trim()
+        .split(',')
+        .map(|x| {
+            x.trim()
+                .parse::<isize>()
+                .unwrap_or_else(|_| panic!("{x} isn't part of a program"))
+        })
+        .collect()
+}
+
+/// Largest address we're prepared to grow memory to.  Anything bigger is far more likely to be
+/// a bug (or a fuzzer) than a real program, and would just exhaust memory.
+pub const MAX_MEMORY: usize = 1 << 24;
+
+/// Ways a program can go wrong.  `address` is always where the offending instruction starts.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VMError {
+    InvalidOpcode { address: usize, value: isize },
+    InvalidMode { address: usize, mode: isize },
+    NegativeAddress { address: usize, target: isize },
+    OutOfMemory { address: usize, target: isize },
+    Overflow { address: usize },
+}
+
+impl Display for VMError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VMError::InvalidOpcode { address, value } => {
+                write!(f, "Invalid opcode {value} at {address}")
+            }
+            VMError::InvalidMode { address, mode } => {
+                write!(f, "Invalid parameter mode {mode} at {address}")
+            }
+            VMError::NegativeAddress { address, target } => {
+                write!(
+                    f,
+                    "Instruction at {address} accessed negative address {target}"
+                )
+            }
+            VMError::OutOfMemory { address, target } => {
+                write!(
+                    f,
+                    "Instruction at {address} accessed {target}, beyond MAX_MEMORY"
+                )
+            }
+            VMError::Overflow { address } => write!(f, "Arithmetic overflow at {address}"),
+        }
+    }
+}
+
+impl std::error::Error for VMError {}
+
+/// Ways running a VM between channels (`thread_vm`, `async_vm`) can fail, beyond the program
+/// itself going wrong
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DriveError {
+    VM(VMError),
+    /// The program wanted input, but every sender had been dropped
+    InputClosed {
+        pointer: usize,
+    },
+    /// The program had output, but nobody was listening any more
+    OutputClosed {
+        value: isize,
+    },
+}
+
+impl Display for DriveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DriveError::VM(error) => write!(f, "{error}"),
+            DriveError::InputClosed { pointer } => {
+                write!(f, "Input at {pointer} closed while waiting for it")
+            }
+            DriveError::OutputClosed { value } => {
+                write!(f, "Output closed before {value} could be sent")
+            }
+        }
     }
 }
 
+impl std::error::Error for DriveError {}
+
+impl From<VMError> for DriveError {
+    fn from(error: VMError) -> Self {
+        DriveError::VM(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ModificationKind {
+    /// Overwrote part of an instruction that has already run at least once
+    Executed,
+    /// Overwrote part of the instruction that is about to run
+    Pending,
+}
+
+/// A write by the program into its own instruction stream
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SelfModification {
+    /// Address of the instruction that did the write
+    pub writer: usize,
+    pub address: usize,
+    pub value: isize,
+    pub kind: ModificationKind,
+}
+
+/// Everything needed to put a VM back exactly where it was
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Snapshot {
+    pub memory: Memory,
+    pub pointer: usize,
+    pub relative_base: isize,
+    pub input: Vec<isize>,
+    pub output: VecDeque<isize>,
+    state: VMState,
+}
+
 #[derive(Debug, Clone)]
 pub struct VM {
-    memory: Vec<isize>,
+    memory: Memory,
     pointer: usize,
     state: VMState,
     relative_base: isize,
     input: Vec<isize>,
     output: VecDeque<isize>, // getting uncomfortable with this.. feels like something subject to major change later
+    // Self modification tracking is opt in, so None unless asked for.  Marks every address that
+    // has been part of an executed instruction.
+    executed: Option<Vec<bool>>,
+    last_write: Option<usize>,
+    modifications: Vec<SelfModification>,
 }
 
 impl VM {
     #[must_use]
     pub fn new(memory: Vec<isize>) -> Self {
-        debug_println!("Creating VM from: {:?}", memory);
+        trace!("Creating VM from: {:?}", memory);
         // The computer's available memory should be much larger than the initial program.
         // Memory beyond the initial program starts with the value 0 and can be read or written like any other memory.
         // (It is invalid to try to access memory at a negative address, though.)
@@ -77,24 +257,133 @@ impl VM {
         // Trade off is memory consumption vs cost of hashing.
 
         VM {
-            memory,
+            memory: Memory::from(memory),
             pointer: 0,
             state: VMState::Initialised,
             relative_base: 0,
             input: vec![],
             output: VecDeque::default(),
+            executed: None,
+            last_write: None,
+            modifications: vec![],
+        }
+    }
+
+    /// Start keeping track of which addresses have been executed, and recording any writes that
+    /// land on executed instructions or the one about to run.  Costs a little on every step.
+    pub fn track_self_modification(&mut self) {
+        if self.executed.is_none() {
+            self.executed = Some(vec![false; self.memory.len()]);
+        }
+    }
+
+    /// Keep memory in pages shared between clones until one of them writes, rather than copying
+    /// it all for every clone.  Only worth it for big memories cloned a lot: every read and write
+    /// costs a little more.  See `memory.rs`.
+    pub fn share_memory(&mut self) {
+        self.memory.share();
+    }
+
+    /// Self modifying writes seen so far, oldest first.  Always empty unless
+    /// `track_self_modification` was called.
+    pub fn self_modifications(&self) -> &[SelfModification] {
+        &self.modifications
+    }
+
+    /// Whether the address has been part of an executed instruction, if we're tracking that
+    pub fn has_executed(&self, address: usize) -> Option<bool> {
+        self.executed
+            .as_ref()
+            .map(|executed| executed.get(address).copied().unwrap_or(false))
+    }
+
+    fn record_execution(&mut self, address: usize, opcode: &OC) {
+        let Some(executed) = self.executed.as_mut() else {
+            return;
+        };
+        let end = address + 1 + opcode.parameter_count();
+        if executed.len() < end {
+            executed.resize(end, false);
         }
+        executed[address..end].fill(true);
+
+        let Some(target) = self.last_write.take() else {
+            return;
+        };
+        let kind = if executed.get(target).copied().unwrap_or(false) {
+            ModificationKind::Executed
+        } else if self.state != VMState::Finished && self.next_instruction().contains(&target) {
+            ModificationKind::Pending
+        } else {
+            return;
+        };
+        let value = self.memory[target];
+        trace!("Instruction at {address} modified {target} to {value} ({kind:?})");
+        self.modifications.push(SelfModification {
+            writer: address,
+            address: target,
+            value,
+            kind,
+        });
+    }
+
+    /// Addresses covered by the instruction under the pointer, as it currently decodes
+    fn next_instruction(&self) -> std::ops::Range<usize> {
+        let width = self
+            .memory
+            .get(self.pointer)
+            .and_then(|raw| try_decode_opcode(*raw))
+            .map_or(1, |opcode| 1 + opcode.parameter_count());
+        self.pointer..self.pointer + width
     }
 
+    /// # Panics
+    ///
+    /// Will panic if the program does something invalid.  See `try_run`.
     pub fn run(&mut self) {
+        if let Err(error) = self.try_run() {
+            panic!("{error}");
+        }
+    }
+
+    /// Run until the program finishes, needs input, or runs off the end of memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid thing the program tries to do.  The VM is left pointing at the
+    /// instruction that failed.
+    pub fn try_run(&mut self) -> Result<(), VMError> {
         self.state = VMState::Running;
         while self.pointer < self.memory.len()
             && self.state != VMState::Finished
             && self.state != VMState::WaitingForInput
         {
-            debug_println!("{:?}", self.memory);
-            self.step();
+            trace!("{:?}", self.memory);
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Like `try_run`, but gives up after `max_steps` instructions, for programs that might never
+    /// stop.  Returns whether the VM stopped by itself.
+    ///
+    /// # Errors
+    ///
+    /// As `try_run`.
+    pub fn try_run_for(&mut self, max_steps: usize) -> Result<bool, VMError> {
+        self.state = VMState::Running;
+        for _ in 0..max_steps {
+            if self.pointer >= self.memory.len()
+                || self.state == VMState::Finished
+                || self.state == VMState::WaitingForInput
+            {
+                return Ok(true);
+            }
+            self.step()?;
         }
+        Ok(self.pointer >= self.memory.len()
+            || self.state == VMState::Finished
+            || self.state == VMState::WaitingForInput)
     }
 
     pub fn finished(&self) -> bool {
@@ -106,24 +395,81 @@ impl VM {
     }
 
     pub fn has_output(&self) -> bool {
-        self.output.len() > 0
+        !self.output.is_empty()
+    }
+
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    pub fn relative_base(&self) -> isize {
+        self.relative_base
+    }
+
+    /// Memory as it stands, including anything it has grown by.  Unlike `get_memory` this never
+    /// grows it.
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Output that hasn't been popped yet, oldest first
+    pub fn output(&self) -> &VecDeque<isize> {
+        &self.output
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory.clone(),
+            pointer: self.pointer,
+            relative_base: self.relative_base,
+            input: self.input.clone(),
+            output: self.output.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Put the VM back to a snapshot.  If self modification tracking is on it starts afresh.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.memory.clone_from(&snapshot.memory);
+        self.pointer = snapshot.pointer;
+        self.relative_base = snapshot.relative_base;
+        self.input.clone_from(&snapshot.input);
+        self.output.clone_from(&snapshot.output);
+        self.state = snapshot.state.clone();
+        if let Some(executed) = self.executed.as_mut() {
+            executed.clear();
+            executed.resize(self.memory.len(), false);
+            self.last_write = None;
+            self.modifications.clear();
+        }
     }
 
     fn set_state(&mut self, state: VMState) {
-        debug_println!("Setting VM state to {:?}", state);
+        trace!("Setting VM state to {:?}", state);
         self.state = state;
     }
 
     pub fn increment_relative_offset<T: PrimInt + Display>(&mut self, input: T) {
-        debug_println!("Incrementing relative_base by {input}");
+        trace!("Incrementing relative_base by {input}");
         self.relative_base += input.to_isize().unwrap();
     }
 
     pub fn push_input<T: PrimInt + Display>(&mut self, input: T) {
-        debug_println!("Adding {input} to input queue");
+        trace!("Adding {input} to input queue");
         self.input.push(input.to_isize().unwrap());
     }
 
+    /// Queue several inputs to be read in the order given, after anything already waiting.
+    /// (`push_input` is last in, first out, which is the wrong way round for streams like text.)
+    pub fn queue_input<I: IntoIterator<Item = isize>>(&mut self, values: I) {
+        let mut queued: Vec<isize> = values.into_iter().collect();
+        trace!("Queueing {:?} behind input {:?}", queued, self.input);
+        queued.reverse();
+        queued.append(&mut self.input);
+        self.input = queued;
+    }
+
     pub fn pop_input(&mut self) -> Result<isize, &'static str> {
         match self.input.pop() {
             Some(x) => Ok(x),
@@ -133,99 +479,166 @@ impl VM {
 
     pub fn pop_output(&mut self) -> Option<isize> {
         let output = self.output.pop_back();
-        debug_println!("Got {:?} from output", output);
+        trace!("Got {:?} from output", output);
         output
     }
 
     pub fn pop_front_output(&mut self) -> Option<isize> {
         let output = self.output.pop_front();
-        debug_println!("Got {:?} from output", output);
+        trace!("Got {:?} from output", output);
         output
     }
 
     pub fn push_output(&mut self, value: isize) {
-        debug_println!("Pushing {value} to output");
+        trace!("Pushing {value} to output");
         self.output.push_back(value);
     }
 
     // I'm going to draw from https://www.reddit.com/r/adventofcode/comments/e8aw9j/2019_day_9_part_1_how_to_fix_203_error/faajho3/
     // I've messed up something here and I like the way that approach shapes the code.
-    fn get_param<T: PrimInt + Display>(&mut self, parameter_number: T) -> isize {
-        debug_println!("Getting from {parameter_number}");
+    fn get_param<T: PrimInt + Display>(&mut self, parameter_number: T) -> Result<isize, VMError> {
+        trace!("Getting from {parameter_number}");
         let mode =
             self.get_memory(self.pointer) / (10 * 10.pow(parameter_number.to_u32().unwrap()));
         let val = self.get_memory(self.pointer + parameter_number.to_usize().unwrap());
         match mode % 10 {
             0 => {
-                let result = self.get_memory(val);
-                debug_println!("Imode 0, Returning: {result}");
-                result
+                let result = self.read(val)?;
+                trace!("Imode 0, Returning: {result}");
+                Ok(result)
             }
             1 => {
-                debug_println!("Imode 1, Returning {val}");
-                val
+                trace!("Imode 1, Returning {val}");
+                Ok(val)
             }
             2 => {
-                let result = self.get_memory(val + self.relative_base);
-                debug_println!("Imode 2, Returning {result}");
-                result
+                let result = self.read(self.relative_address(val)?)?;
+                trace!("Imode 2, Returning {result}");
+                Ok(result)
             }
-            _ => panic!("Invalid parameter mode: {}", mode % 10),
+            _ => Err(VMError::InvalidMode {
+                address: self.pointer,
+                mode: mode % 10,
+            }),
         }
     }
 
-    fn set_param<T: PrimInt + Display>(&mut self, parameter_number: T, set_to: T) {
-        debug_println!("Getting from {parameter_number}");
+    fn set_param<T: PrimInt + Display>(
+        &mut self,
+        parameter_number: T,
+        set_to: T,
+    ) -> Result<(), VMError> {
+        trace!("Getting from {parameter_number}");
         let mode =
             self.get_memory(self.pointer) / (10 * 10.pow(parameter_number.to_u32().unwrap()));
         let val = self.get_memory(self.pointer + parameter_number.to_usize().unwrap());
-        match mode % 10 {
+        let target = match mode % 10 {
             0 => {
-                debug_println!("Imode 0, Setting: {val} to {set_to}");
-                self.set_memory(val, set_to.to_isize().unwrap())
+                trace!("Imode 0, Setting: {val} to {set_to}");
+                val
             }
             2 => {
-                let target = val + self.relative_base;
-                debug_println!("Imode 2, Setting {target} to {set_to}");
-                self.set_memory(target, set_to.to_isize().unwrap())
+                let target = self.relative_address(val)?;
+                trace!("Imode 2, Setting {target} to {set_to}");
+                target
+            }
+            _ => {
+                return Err(VMError::InvalidMode {
+                    address: self.pointer,
+                    mode: mode % 10,
+                })
             }
-            _ => panic!("Invalid parameter mode: {}", mode % 10),
+        };
+        self.check_address(target)?;
+        self.set_memory(target, set_to.to_isize().unwrap());
+        self.note_write(target);
+        Ok(())
+    }
+
+    fn relative_address(&self, offset: isize) -> Result<isize, VMError> {
+        offset
+            .checked_add(self.relative_base)
+            .ok_or(VMError::Overflow {
+                address: self.pointer,
+            })
+    }
+
+    /// Make sure the program is allowed to touch `target`, before we do anything with it
+    fn check_address(&self, target: isize) -> Result<usize, VMError> {
+        match usize::try_from(target).ok() {
+            None => Err(VMError::NegativeAddress {
+                address: self.pointer,
+                target,
+            }),
+            Some(valid) if valid >= MAX_MEMORY => Err(VMError::OutOfMemory {
+                address: self.pointer,
+                target,
+            }),
+            Some(valid) => Ok(valid),
+        }
+    }
+
+    fn read(&mut self, target: isize) -> Result<isize, VMError> {
+        self.check_address(target)?;
+        Ok(self.get_memory(target))
+    }
+
+    fn note_write<T: PrimInt + Display>(&mut self, target: T) {
+        if self.executed.is_some() {
+            self.last_write = Some(target.to_usize().unwrap());
         }
     }
 
     pub fn set_memory<T: PrimInt + Display>(&mut self, address: T, value: isize) {
         let target = address.to_usize().unwrap();
-        if target > self.memory.len() - 1 {
-            debug_println!("Expanding memory to {target}");
-            self.memory.resize(target + 1, 0);
+        if target >= self.memory.len() {
+            trace!("Expanding memory to {target}");
+            self.memory.grow(target + 1);
         }
-        debug_println!("Setting {address} to {value}");
-        self.memory[target] = value;
+        trace!("Setting {address} to {value}");
+        self.memory.set(target, value);
     }
 
     pub fn get_memory<T: PrimInt + Display>(&mut self, address: T) -> isize {
         let target = address.to_usize().unwrap();
-        if target > self.memory.len() - 1 {
-            debug_println!("Expanding memory to {target}");
-            self.memory.resize(target + 1, 0);
+        if target >= self.memory.len() {
+            trace!("Expanding memory to {target}");
+            self.memory.grow(target + 1);
         }
 
         self.memory[target]
     }
 
-    fn set_pointer<T: PrimInt + Display>(&mut self, value: T) {
-        debug_println!("Setting pointer to {value}");
-        self.pointer = value.to_usize().unwrap();
+    fn set_pointer(&mut self, value: isize) -> Result<(), VMError> {
+        trace!("Setting pointer to {value}");
+        self.pointer = self.check_address(value)?;
+        Ok(())
     }
 
     fn increment_pointer<T: PrimInt + Display>(&mut self, value: T) {
-        debug_println!("Incrementing pointer by {value}");
+        trace!("Incrementing pointer by {value}");
         self.pointer += value.to_usize().unwrap();
     }
 
-    fn step(&mut self) {
+    /// Execute the single instruction under the pointer
+    ///
+    /// # Errors
+    ///
+    /// If the instruction is invalid, or tries to do something invalid.  The pointer isn't moved,
+    /// so the failing instruction can be inspected.
+    pub fn step(&mut self) -> Result<(), VMError> {
         // From searching online, dynamic dispatch adds a bunch of undesirable overhead.
-        let opcode = decode_opcode(self.get_memory(self.pointer));
+        let instruction_address = self.pointer;
+        let raw = self.get_memory(self.pointer);
+        let Some(opcode) = try_decode_opcode(raw) else {
+            return Err(VMError::InvalidOpcode {
+                address: self.pointer,
+                value: raw,
+            });
+        };
+        let overflow = VMError::Overflow {
+            address: self.pointer,
+        };
         // eww opcode.opcode?
         match opcode {
             OC::Add => {
@@ -236,11 +649,11 @@ impl VM {
                 positions from which you should read the input values, and the third
                 indicates the position at which the output should be stored.
                  */
-                debug_println!("{:?}", &opcode);
-                let a = self.get_param(1);
-                let b = self.get_param(2);
-                debug_println!("{a} + {b}");
-                self.set_param(3, a + b);
+                trace!("{:?}", &opcode);
+                let a = self.get_param(1)?;
+                let b = self.get_param(2)?;
+                trace!("{a} + {b}");
+                self.set_param(3, a.checked_add(b).ok_or(overflow)?)?;
                 self.increment_pointer(4);
             }
             OC::Mul => {
@@ -249,15 +662,15 @@ impl VM {
                 inputs instead of adding them. Again, the three integers after the
                 opcode indicate where the inputs and outputs are, not their values.
                  */
-                debug_println!("{:?}", &opcode);
-                let a = self.get_param(1);
-                let b = self.get_param(2);
-                debug_println!("{:?}: {a} * {b}", &opcode);
-                self.set_param(3, a * b);
+                trace!("{:?}", &opcode);
+                let a = self.get_param(1)?;
+                let b = self.get_param(2)?;
+                trace!("{:?}: {a} * {b}", &opcode);
+                self.set_param(3, a.checked_mul(b).ok_or(overflow)?)?;
                 self.increment_pointer(4);
             }
             OC::End => {
-                debug_println!("{:?}. Ending program", opcode);
+                trace!("{:?}. Ending program", opcode);
                 self.state = VMState::Finished;
             }
             OC::Input => {
@@ -266,13 +679,19 @@ impl VM {
                 by its only parameter. For example, the instruction 3,50 would take an input
                 value and store it at address 50.
                 */
-                debug_println!("{:?}", &opcode);
+                trace!("{:?}", &opcode);
                 if let Ok(input) = self.pop_input() {
-                    debug_println!("{:?}, Got input {input}", opcode);
-                    self.set_param(1, input);
+                    trace!("{:?}, Got input {input}", opcode);
+                    if let Err(error) = self.set_param(1, input) {
+                        // Leave the input for whoever retries from here
+                        self.input.push(input);
+                        return Err(error);
+                    }
                     self.increment_pointer(2);
                 } else {
+                    // Nothing actually executed, so nothing to record either
                     self.set_state(VMState::WaitingForInput);
+                    return Ok(());
                 }
             }
             OC::Output => {
@@ -280,11 +699,11 @@ impl VM {
                 Opcode 4 outputs the value of its only parameter. For example, the
                 instruction 4,50 would output the value at address 50.
                 */
-                debug_println!("{:?}", &opcode);
-                let output = self.get_param(1);
-                debug_println!("{:?}: output: {:?}", &opcode, output);
+                trace!("{:?}", &opcode);
+                let output = self.get_param(1)?;
+                trace!("{:?}: output: {:?}", &opcode, output);
                 self.push_output(output);
-                debug_println!("Output Queue: {:?}", self.output);
+                trace!("Output Queue: {:?}", self.output);
                 self.increment_pointer(2);
             }
             OC::JumpIfTrue => {
@@ -293,14 +712,14 @@ impl VM {
                 sets the instruction pointer to the value from the second parameter.
                 Otherwise, it does nothing.
                 */
-                debug_println!("{:?}", &opcode);
-                let a = self.get_param(1);
+                trace!("{:?}", &opcode);
+                let a = self.get_param(1)?;
                 if a != 0 {
-                    let target = self.get_param(2);
-                    debug_println!("{a} != 0, jumping to {target}");
-                    self.set_pointer(target);
+                    let target = self.get_param(2)?;
+                    trace!("{a} != 0, jumping to {target}");
+                    self.set_pointer(target)?;
                 } else {
-                    debug_println!("{a} == 0.  Not jumping");
+                    trace!("{a} == 0.  Not jumping");
                     self.increment_pointer(3);
                 }
             }
@@ -309,15 +728,15 @@ impl VM {
                 Opcode 6 is jump-if-false: if the first parameter is zero, it sets the
                 instruction pointer to the value from the second parameter. Otherwise, it does nothing.
                 */
-                debug_println!("{:?}", &opcode);
-                let a = self.get_param(1);
+                trace!("{:?}", &opcode);
+                let a = self.get_param(1)?;
 
                 if a == 0 {
-                    let target = self.get_param(2);
-                    debug_println!("{a} == 0, jumping to {target}");
-                    self.set_pointer(target);
+                    let target = self.get_param(2)?;
+                    trace!("{a} == 0, jumping to {target}");
+                    self.set_pointer(target)?;
                 } else {
-                    debug_println!("{a} != 0.  Not jumping");
+                    trace!("{a} != 0.  Not jumping");
                     self.increment_pointer(3);
                 }
             }
@@ -326,17 +745,17 @@ impl VM {
                 Opcode 7 is less than: if the first parameter is less than the second parameter,
                 it stores 1 in the position given by the third parameter. Otherwise, it stores 0.
                 */
-                debug_println!("{:?}", &opcode);
-                let a = self.get_param(1);
-                let b = self.get_param(2);
-                debug_println!("{a} < {b} ?");
+                trace!("{:?}", &opcode);
+                let a = self.get_param(1)?;
+                let b = self.get_param(2)?;
+                trace!("{a} < {b} ?");
 
                 if a < b {
-                    debug_println!("Yes!");
-                    self.set_param(3, 1);
+                    trace!("Yes!");
+                    self.set_param(3, 1)?;
                 } else {
-                    debug_println!("No!");
-                    self.set_param(3, 0);
+                    trace!("No!");
+                    self.set_param(3, 0)?;
                 }
                 self.increment_pointer(4);
             }
@@ -345,16 +764,16 @@ impl VM {
                  Opcode 8 is equals: if the first parameter is equal to the second parameter,
                  it stores 1 in the position given by the third parameter. Otherwise, it stores 0.
                 */
-                debug_println!("{:?}", &opcode);
-                let a = self.get_param(1);
-                let b = self.get_param(2);
-                debug_println!("{a} == {b} ?");
+                trace!("{:?}", &opcode);
+                let a = self.get_param(1)?;
+                let b = self.get_param(2)?;
+                trace!("{a} == {b} ?");
                 if a == b {
-                    debug_println!("Yes!");
-                    self.set_param(3, 1);
+                    trace!("Yes!");
+                    self.set_param(3, 1)?;
                 } else {
-                    debug_println!("No!");
-                    self.set_param(3, 0);
+                    trace!("No!");
+                    self.set_param(3, 0)?;
                 }
                 self.increment_pointer(4);
             }
@@ -363,20 +782,37 @@ impl VM {
                 Opcode 9 adjusts the relative base by the value of its only parameter.
                 The relative base increases (or decreases, if the value is negative) by the value of the parameter.
                  */
-                debug_println!("{:?}", &opcode);
-                let offset_increment = self.get_param(1);
-                debug_println!("Incrementing offset by {offset_increment}");
-                self.increment_relative_offset(offset_increment);
-                debug_println!("Current offset {}", self.relative_base);
+                trace!("{:?}", &opcode);
+                let offset_increment = self.get_param(1)?;
+                trace!("Incrementing offset by {offset_increment}");
+                self.relative_base = self
+                    .relative_base
+                    .checked_add(offset_increment)
+                    .ok_or(overflow)?;
+                trace!("Current offset {}", self.relative_base);
                 self.increment_pointer(2);
             }
         }
+        self.record_execution(instruction_address, &opcode);
+        Ok(())
+    }
+}
+
+impl From<Snapshot> for VM {
+    fn from(snapshot: Snapshot) -> Self {
+        let mut vm = VM::new(vec![]);
+        vm.restore(&snapshot);
+        vm
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::differential::{assert_lockstep, lockstep, Agreement};
+    use crate::generate::{any_memory, well_formed_program};
+    use crate::memory::PAGE_SIZE;
+    use proptest::prelude::*;
     use rstest::*;
 
     #[test]
@@ -416,7 +852,7 @@ mod tests {
     fn test_op_five(#[case] input: Vec<isize>, #[case] expected: usize) {
         // five = JumpIfTrue. if first param is non-zero, should set pointer to second param
         let mut test_vm = VM::new(input);
-        test_vm.step();
+        test_vm.step().unwrap();
         assert_eq!(test_vm.pointer, expected);
     }
 
@@ -428,7 +864,7 @@ mod tests {
     fn test_op_six(#[case] input: Vec<isize>, #[case] expected: usize) {
         // five = JumpIfFalse. if first param is non-zero, should set pointer to second param
         let mut test_vm = VM::new(input);
-        test_vm.step();
+        test_vm.step().unwrap();
         assert_eq!(test_vm.pointer, expected);
     }
 
@@ -440,7 +876,7 @@ mod tests {
     fn test_op_seven(#[case] input: Vec<isize>, #[case] expected: isize) {
         // seven = LessThan. If first param less than second, store 1 in position from third
         let mut test_vm = VM::new(input);
-        test_vm.step();
+        test_vm.step().unwrap();
         assert_eq!(test_vm.memory[4], expected); // bad way to test!
     }
 
@@ -452,7 +888,7 @@ mod tests {
     fn test_op_eight(#[case] input: Vec<isize>, #[case] expected: isize) {
         // eight = equals. If first param = second, store 1 in position from third
         let mut test_vm = VM::new(input);
-        test_vm.step();
+        test_vm.step().unwrap();
         assert_eq!(test_vm.memory[4], expected); // bad way to test!
     }
 
@@ -462,7 +898,7 @@ mod tests {
     fn test_op_nine(#[case] input: Vec<isize>, #[case] expected: isize) {
         // nine updates relative base by provided increment
         let mut test_vm = VM::new(input);
-        test_vm.step();
+        test_vm.step().unwrap();
         assert_eq!(test_vm.relative_base, expected); // bad way to test!
     }
 
@@ -580,7 +1016,7 @@ mod tests {
         let output = vm.pop_output().unwrap();
         // Should be 16 digits long.. divide by 1000000000000000.
         // If it's between 1 and 9, it's a 16 digit number.
-        debug_println!("{}", output / 1000000000000000);
+        trace!("{}", output / 1000000000000000);
         assert!((1..10).contains(&(output / 1000000000000000)));
     }
 
@@ -592,6 +1028,212 @@ mod tests {
         assert_eq!(output, 1125899906842624);
     }
 
+    #[test]
+    fn test_self_modification_not_tracked_by_default() {
+        let mut vm = VM::new(vec![1, 1, 1, 4, 99, 5, 6, 0, 99]);
+        vm.run();
+        assert_eq!(vm.has_executed(0), None);
+        assert!(vm.self_modifications().is_empty());
+    }
+
+    #[test]
+    fn test_self_modification_day2_example() {
+        // The add turns the 99 at 4 into a 2, which then overwrites the add itself
+        let mut vm = VM::new(vec![1, 1, 1, 4, 99, 5, 6, 0, 99]);
+        vm.track_self_modification();
+        vm.run();
+        assert_eq!(
+            vm.self_modifications(),
+            &[
+                SelfModification {
+                    writer: 0,
+                    address: 4,
+                    value: 2,
+                    kind: ModificationKind::Pending
+                },
+                SelfModification {
+                    writer: 4,
+                    address: 0,
+                    value: 30,
+                    kind: ModificationKind::Executed
+                },
+            ]
+        );
+        assert_eq!(vm.has_executed(8), Some(true));
+        assert_eq!(vm.has_executed(9), Some(false));
+    }
+
+    #[rstest]
+    #[case(vec![1, 0, 0, 0, 99], vec![(0, ModificationKind::Executed)])] // overwrites its own opcode
+    #[case(vec![1101, 1, 1, 7, 99, 0, 0, 0], vec![])] // writes to data
+    #[case(vec![3, 3, 104, 0, 99], vec![(3, ModificationKind::Pending)])] // patches the next operand
+    fn test_self_modification_kinds(
+        #[case] memory: Vec<isize>,
+        #[case] expected: Vec<(usize, ModificationKind)>,
+    ) {
+        let mut vm = VM::new(memory);
+        vm.track_self_modification();
+        vm.push_input(7);
+        vm.run();
+        let found: Vec<(usize, ModificationKind)> = vm
+            .self_modifications()
+            .iter()
+            .map(|m| (m.address, m.kind))
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    // Tracking self modification is meant to be purely observational, so a tracked VM should
+    // never diverge from a plain one.
+    #[rstest]
+    #[case(vec![1, 1, 1, 4, 99, 5, 6, 0, 99], vec![])]
+    #[case(vec![3,9,8,9,10,9,4,9,99,-1,8], vec![8])]
+    #[case(vec![3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], vec![0])]
+    #[case(vec![3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,
+        1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,
+        999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], vec![9])]
+    #[case(vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99], vec![])]
+    fn test_tracking_matches_reference(#[case] memory: Vec<isize>, #[case] input: Vec<isize>) {
+        let mut tracked = VM::new(memory.clone());
+        tracked.track_self_modification();
+        let agreement = assert_lockstep(VM::new(memory), tracked, &input);
+        assert!(matches!(agreement, Agreement::Finished { .. }));
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut vm = VM::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8]);
+        let snapshot = vm.snapshot();
+        vm.push_input(8);
+        vm.run();
+        vm.restore(&snapshot);
+        vm.push_input(7);
+        vm.run();
+        assert_eq!(vm.pop_output(), Some(0));
+        assert_eq!(VM::from(snapshot).memory()[9], -1);
+    }
+
+    #[test]
+    fn test_clones_are_independent() {
+        // Writes its input to 9, and 1 to 2000 (growing memory on the way)
+        let mut vm = VM::new(vec![3, 9, 1101, 0, 1, 2000, 99, 0, 0, 0]);
+        let mut fork = vm.clone();
+        vm.push_input(5);
+        vm.run();
+        fork.push_input(6);
+        fork.run();
+        assert_eq!((vm.memory()[9], fork.memory()[9]), (5, 6));
+        assert_eq!(fork.memory()[2000], 1);
+
+        // Once shared, a clone shares all of its memory until something's written
+        vm.share_memory();
+        let mut fork = vm.clone();
+        let pages = 2001usize.div_ceil(PAGE_SIZE);
+        assert_eq!(fork.memory().shared_pages(vm.memory()), pages);
+        fork.set_memory(2000, 2);
+        assert_eq!(fork.memory().shared_pages(vm.memory()), pages - 1);
+        assert_eq!(vm.memory()[2000], 1);
+    }
+
+    #[rstest]
+    #[case(vec![1105, 1, 0], 100, false)]
+    #[case(vec![1101, 1, 1, 5, 99, 0], 2, true)]
+    #[case(vec![1101, 1, 1, 5, 99, 0], 1, false)]
+    #[case(vec![3, 0, 99], 100, true)]
+    fn test_try_run_for(
+        #[case] program: Vec<isize>,
+        #[case] max_steps: usize,
+        #[case] expected: bool,
+    ) {
+        let mut vm = VM::new(program);
+        assert_eq!(vm.try_run_for(max_steps), Ok(expected));
+    }
+
+    #[test]
+    fn test_queue_input() {
+        // Echo three values
+        let mut vm = VM::new(vec![3, 13, 4, 13, 3, 13, 4, 13, 3, 13, 4, 13, 99]);
+        vm.push_input(1);
+        vm.queue_input([2, 3]);
+        vm.run();
+        assert_eq!(vm.output(), &VecDeque::from([1, 2, 3]));
+    }
+
+    #[rstest]
+    #[case(vec![42], VMError::InvalidOpcode { address: 0, value: 42 })]
+    #[case(vec![301, 0, 0, 0], VMError::InvalidMode { address: 0, mode: 3 })]
+    #[case(vec![11101, 1, 1, 0], VMError::InvalidMode { address: 0, mode: 1 })]
+    #[case(vec![1, -1, 0, 0], VMError::NegativeAddress { address: 0, target: -1 })]
+    #[case(vec![1105, 1, -5], VMError::NegativeAddress { address: 0, target: -5 })]
+    #[case(vec![1101, 0, 0, 1 << 30], VMError::OutOfMemory { address: 0, target: 1 << 30 })]
+    #[case(vec![1102, isize::MAX, 2, 0], VMError::Overflow { address: 0 })]
+    fn test_errors(#[case] memory: Vec<isize>, #[case] expected: VMError) {
+        let mut vm = VM::new(memory);
+        assert_eq!(vm.try_run(), Err(expected));
+        assert_eq!(vm.pointer(), 0);
+    }
+
+    /// Step until the VM stops, fails, or `max_steps` is up, returning how many steps ran
+    fn run_for(vm: &mut VM, max_steps: usize) -> usize {
+        for steps in 0..max_steps {
+            if vm.finished() || vm.needs_input() || vm.pointer() >= vm.memory().len() {
+                return steps;
+            }
+            if vm.step().is_err() {
+                return steps + 1;
+            }
+        }
+        max_steps
+    }
+
+    proptest! {
+        #[test]
+        fn prop_never_panics(
+            memory in any_memory(),
+            input in prop::collection::vec(any::<isize>(), 0..4),
+        ) {
+            let mut vm = VM::new(memory);
+            vm.track_self_modification();
+            for value in input {
+                vm.push_input(value);
+            }
+            run_for(&mut vm, 1000);
+        }
+
+        #[test]
+        fn prop_snapshot_restore_mid_run(
+            memory in well_formed_program(),
+            input in prop::collection::vec(-5isize..5, 0..4),
+            split in 0usize..200,
+        ) {
+            let mut straight = VM::new(memory.clone());
+            let mut first_half = VM::new(memory);
+            for value in input {
+                straight.push_input(value);
+                first_half.push_input(value);
+            }
+            run_for(&mut straight, 500);
+
+            let steps = run_for(&mut first_half, split);
+            let mut second_half = VM::from(first_half.snapshot());
+            run_for(&mut second_half, 500 - steps);
+
+            prop_assert_eq!(second_half.output(), straight.output());
+            prop_assert_eq!(second_half.snapshot(), straight.snapshot());
+        }
+
+        #[test]
+        fn prop_tracking_never_diverges(
+            memory in well_formed_program(),
+            input in prop::collection::vec(-5isize..5, 0..4),
+        ) {
+            let mut tracked = VM::new(memory.clone());
+            tracked.track_self_modification();
+            let result = lockstep(VM::new(memory), tracked, &input, 500);
+            prop_assert!(result.is_ok(), "{}", result.unwrap_err());
+        }
+    }
+
     #[rstest]
     #[case(1001, OC::Add)]
     #[case(1002, OC::Mul)]
@@ -604,7 +1246,7 @@ mod tests {
     #[case(1009, OC::RelativeBaseOffset)]
     #[case(1099, OC::End)]
     fn test_opcode_creation(#[case] test_case: isize, #[case] expected: OC) {
-        let opcode = decode_opcode(test_case);
+        let opcode = try_decode_opcode(test_case).unwrap();
         assert_eq!(opcode, expected);
     }
 }